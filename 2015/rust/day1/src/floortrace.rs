@@ -0,0 +1,151 @@
+use std::fmt;
+use std::io;
+use std::io::ErrorKind;
+use std::io::Read;
+
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// What happened while following the instructions, gathered in one pass.
+/// Positions count characters from 1, and position 0 is the start on floor 0.
+#[derive(Debug)]
+pub struct FloorTrace {
+	pub final_floor: i64,
+	/// Where floor -1, -2, -3 and so on were first reached, in that order.
+	first_negative_entries: Vec<EntryRun>,
+	/// The highest floor and where it was first reached.
+	pub max_floor: (i64, u64),
+	/// The lowest floor and where it was first reached.
+	pub min_floor: (i64, u64),
+	pub other_characters: u64,
+	pub length: u64,
+}
+
+/// Positions that are evenly spaced, so that a long way down the stairs takes
+/// the same memory as a single step.
+#[derive(Debug)]
+struct EntryRun {
+	position: u64,
+	step: u64,
+	count: u64,
+}
+
+impl EntryRun {
+	fn last(&self) -> u64 {
+		self.position + self.step * (self.count - 1)
+	}
+}
+
+impl FloorTrace {
+	/// Reads the instructions a block at a time, so the input never has to fit
+	/// in memory. Input is read as UTF-8, where every byte that does not
+	/// continue a multi-byte character starts a new character.
+	pub fn from_reader<R: Read>(mut reader: R) -> io::Result<FloorTrace> {
+		let mut trace = FloorTrace {
+			final_floor: 0,
+			first_negative_entries: Vec::new(),
+			max_floor: (0, 0),
+			min_floor: (0, 0),
+			other_characters: 0,
+			length: 0,
+		};
+
+		let mut buffer = vec![0; BUFFER_SIZE];
+
+		loop {
+			let read = match reader.read(&mut buffer) {
+				Ok(0)                                                => break,
+				Ok(read)                                             => read,
+				Err(ref err) if err.kind() == ErrorKind::Interrupted => continue,
+				Err(err)                                             => return Err(err),
+			};
+
+			for &b in &buffer[..read] {
+				trace.push(b);
+			}
+		}
+
+		return Ok(trace);
+	}
+
+	fn push(&mut self, b: u8) {
+		if b & 0xC0 == 0x80 {
+			return;
+		}
+
+		self.length += 1;
+
+		match b {
+			b'(' => self.final_floor += 1,
+			b')' => self.final_floor -= 1,
+			_    => {
+				self.other_characters += 1;
+				return;
+			},
+		};
+
+		if self.final_floor > self.max_floor.0 {
+			self.max_floor = (self.final_floor, self.length);
+		}
+
+		if self.final_floor < self.min_floor.0 {
+			self.min_floor = (self.final_floor, self.length);
+			self.push_negative_entry();
+		}
+	}
+
+	fn push_negative_entry(&mut self) {
+		let position = self.length;
+
+		if let Some(run) = self.first_negative_entries.last_mut() {
+			if run.count == 1 || position - run.last() == run.step {
+				run.step = position - run.last();
+				run.count += 1;
+				return;
+			}
+		}
+
+		self.first_negative_entries.push(EntryRun {
+			position: position,
+			step: 0,
+			count: 1,
+		});
+	}
+
+	/// Where the basement was first entered.
+	pub fn first_basement_position(&self) -> Option<u64> {
+		self.first_entry_to(-1)
+	}
+
+	/// Where the floor was first reached, for floors below the ground.
+	pub fn first_entry_to(&self, floor: i64) -> Option<u64> {
+		if floor >= 0 {
+			return None;
+		}
+
+		let mut index = (-(floor + 1)) as u64;
+		for run in &self.first_negative_entries {
+			if index < run.count {
+				return Some(run.position + run.step * index);
+			}
+
+			index -= run.count;
+		}
+
+		None
+	}
+}
+
+impl fmt::Display for FloorTrace {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "final floor: {}", self.final_floor)?;
+		writeln!(f, "highest floor: {} at {}", self.max_floor.0, self.max_floor.1)?;
+		writeln!(f, "lowest floor: {} at {}", self.min_floor.0, self.min_floor.1)?;
+
+		match self.first_basement_position() {
+			Some(position) => writeln!(f, "first basement entry: {}", position)?,
+			None           => writeln!(f, "first basement entry: never")?,
+		};
+
+		write!(f, "characters: {} ({} not parentheses)", self.length, self.other_characters)
+	}
+}
@@ -1,52 +1,92 @@
+use std::env;
 use std::fs::File;
-use std::io::Result;
+use std::io;
 use std::io::Read;
+use std::process;
 
-fn read_file(path: &str) -> Result<String> {
-    let mut file = try!(File::open(path));
-    let mut content = String::new();
-    try!(file.read_to_string(&mut content));
-    Ok(content)
+mod floortrace;
+
+use floortrace::FloorTrace;
+
+const USAGE: &'static str = "usage: day1 [--trace] [--floor NEGATIVE_FLOOR]... [PATH | -]";
+
+struct Options {
+	path: String,
+	trace: bool,
+	floors: Vec<i64>,
 }
 
-fn get_final_floor(input: &str) -> i32 {
-	let mut current_floor = 0;
-	for c in input.chars() {
-		let delta = match c {
-			'(' => 1,
-			')' => -1,
-            _ => 0
+fn parse_options(args: &[String]) -> Result<Options, String> {
+	let mut options = Options {
+		path: "input.txt".to_string(),
+		trace: false,
+		floors: Vec::new(),
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match &arg[..] {
+			"--trace" => options.trace = true,
+			"--floor" => {
+				match args.next().map(|value| value.parse::<i64>()) {
+					Some(Ok(floor)) if floor < 0 => options.floors.push(floor),
+					_ => return Err("Expected a floor below the ground after --floor.".to_string()),
+				};
+			},
+			_ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+			_ => options.path = arg.clone(),
 		};
-		current_floor += delta;
 	}
 
-	return current_floor;
+	return Ok(options);
 }
 
-fn get_first_basement_position(input: &str) -> Option<i32> {
-	let mut current_floor = 0;
-	let mut position = 1;
-	for c in input.chars() {
-		let delta = match c {
-			'(' => 1,
-			')' => -1,
-            _ => 0
-		};
-		current_floor += delta;
-		if(current_floor < 0) {
-			return Some(position);
-		}
-
-		position += 1;
-	}
+/// Traces the file, or standard input for "-", without reading it all first.
+fn trace_path(path: &str) -> io::Result<FloorTrace> {
+	let reader: Box<dyn Read> = match path {
+		"-" => Box::new(io::stdin()),
+		_ => Box::new(File::open(path)?),
+	};
 
-	return None;
+	return FloorTrace::from_reader(reader);
 }
 
 fn main() {
 	println!("Advent of Code - day 1");
 
-	let input = read_file("input.txt").unwrap();
-	println!("Part 1 answer: {}", get_final_floor(&input));
-	println!("Part 2 answer: {}", get_first_basement_position(&input).unwrap());
+	let args: Vec<String> = env::args().skip(1).collect();
+	let options = match parse_options(&args) {
+		Ok(options) => options,
+		Err(err) => {
+			eprintln!("{}\n{}", err, USAGE);
+			process::exit(2);
+		},
+	};
+
+	let trace = match trace_path(&options.path) {
+		Ok(trace) => trace,
+		Err(err) => {
+			eprintln!("{}", err);
+			process::exit(1);
+		},
+	};
+
+	// Describe the trace instead of solving the puzzle.
+	if options.trace || !options.floors.is_empty() {
+		if options.trace {
+			println!("{}", trace);
+		}
+
+		for &floor in &options.floors {
+			match trace.first_entry_to(floor) {
+				Some(position) => println!("floor {} first reached at {}", floor, position),
+				None => println!("floor {} never reached", floor),
+			};
+		}
+
+		return;
+	}
+
+	println!("Part 1 answer: {}", trace.final_floor);
+	println!("Part 2 answer: {}", trace.first_basement_position().unwrap());
 }
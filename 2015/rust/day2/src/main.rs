@@ -1,7 +1,19 @@
+use std::cmp::max;
 use std::cmp::min;
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::Result;
 use std::io::Read;
+use std::process;
+
+mod wrapping;
+
+use wrapping::GiftBagPolicy;
+use wrapping::PackingReport;
+use wrapping::StandardPolicy;
+use wrapping::VolumeRibbonPolicy;
+use wrapping::WrappingPolicy;
 
 struct Dimensions {
 	l: i32,
@@ -9,14 +21,88 @@ struct Dimensions {
 	h: i32,
 }
 
-fn parse_dimensions(input: &str) -> Vec<Dimensions> {
+/// Why a line could not be read as dimensions. Lines are numbered from 1.
+#[derive(Debug)]
+enum DimensionsParseError {
+	WrongNumberOfDimensions(usize, usize),
+	NotANumber(usize, String),
+	NotPositive(usize, i32),
+}
+
+impl fmt::Display for DimensionsParseError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			DimensionsParseError::WrongNumberOfDimensions(line, count) => write!(f, "Line {} has {} dimensions instead of 3.", line, count),
+			DimensionsParseError::NotANumber(line, ref piece) => write!(f, "Line {} has a dimension that is not a number: '{}'.", line, piece),
+			DimensionsParseError::NotPositive(line, dimension) => write!(f, "Line {} has a dimension that is not positive: {}.", line, dimension),
+		}
+	}
+}
+
+impl Dimensions {
+	/// Each dimension is below 2^31, so a side is below 2^62 and always fits.
+	fn side_areas(&self) -> [u64; 3] {
+		let (l, w, h) = (self.l as u64, self.w as u64, self.h as u64);
+		[l*w, w*h, h*l]
+	}
+
+	/// None if the area does not fit in a u64.
+	fn surface_area(&self) -> Option<u64> {
+		let mut total: u64 = 0;
+		for area in self.side_areas().iter() {
+			total = total.checked_add(area.checked_mul(2)?)?;
+		}
+
+		Some(total)
+	}
+
+	fn smallest_side(&self) -> u64 {
+		let sides = self.side_areas();
+		min(min(sides[0], sides[1]), sides[2])
+	}
+
+	fn largest_side(&self) -> u64 {
+		let sides = self.side_areas();
+		max(max(sides[0], sides[1]), sides[2])
+	}
+
+	fn smallest_perimeter(&self) -> u64 {
+		let (l, w, h) = (self.l as u64, self.w as u64, self.h as u64);
+		let perim_a = 2*l+2*w;
+		let perim_b = 2*w+2*h;
+		let perim_c = 2*h+2*l;
+		min(min(perim_a, perim_b), perim_c)
+	}
+
+	/// None if the volume does not fit in a u64.
+	fn volume(&self) -> Option<u64> {
+		(self.l as u64 * self.w as u64).checked_mul(self.h as u64)
+	}
+}
+
+fn parse_dimensions(input: &str) -> std::result::Result<Vec<Dimensions>, DimensionsParseError> {
 	let mut all: Vec<Dimensions> = Vec::new();
-	for line in input.lines() {
-		
-		let dimensions: Vec<i32> = line
-			.split('x')
-			.map(|d| d.parse::<i32>().unwrap())
-			.collect();
+	for (index, line) in input.lines().enumerate() {
+		let line_number = index + 1;
+		let pieces: Vec<&str> = line.trim().split('x').collect();
+		if pieces.len() != 3 {
+			return Err(DimensionsParseError::WrongNumberOfDimensions(line_number, pieces.len()));
+		}
+
+		let mut dimensions: Vec<i32> = Vec::new();
+		for piece in pieces {
+			let dimension = match piece.parse::<i32>() {
+				Ok(dimension) => dimension,
+				Err(_)        => return Err(DimensionsParseError::NotANumber(line_number, piece.to_string())),
+			};
+
+			if dimension <= 0 {
+				return Err(DimensionsParseError::NotPositive(line_number, dimension));
+			}
+
+			dimensions.push(dimension);
+		}
+
 		let l = dimensions[0];
 		let w = dimensions[1];
 		let h = dimensions[2];
@@ -24,7 +110,7 @@ fn parse_dimensions(input: &str) -> Vec<Dimensions> {
 		all.push(Dimensions { l: l, w: w, h: h});
 	}
 
-	all
+	Ok(all)
 }
 
 fn read_file(path: &str) -> Result<String> {
@@ -35,41 +121,96 @@ fn read_file(path: &str) -> Result<String> {
     Ok(content)
 }
 
-fn get_total_square_feet(dimensions: &Vec<Dimensions>) -> i32 {
-	let mut total = 0;
-	for d in dimensions {
-		let side_a = d.l*d.w;
-		let side_b = d.w*d.h;
-		let side_c = d.h*d.l;
-		let min_side = min(min(side_a, side_b), side_c);
-
-		total += (2*side_a) + (2*side_b) + (2*side_c) + min_side;
-	}
+const USAGE: &'static str = "usage: day2 [--policy standard|gift-bag|volume-ribbon] [--feet-per-cubic-foot N] [--report]";
 
-	total
+struct Options {
+	policy: Option<String>,
+	feet_per_cubic_foot: u64,
+	report: bool,
 }
 
-fn get_total_length(dimensions: &Vec<Dimensions>) -> i32 {
-	let mut total = 0;
-	for d in dimensions {
-		let perim_a = 2*d.l+2*d.w;
-		let perim_b = 2*d.w+2*d.h;
-		let perim_c = 2*d.h+2*d.l;
-		let min_perim = min(min(perim_a, perim_b), perim_c);
+fn parse_options(args: &[String]) -> std::result::Result<Options, String> {
+	let mut options = Options {
+		policy: None,
+		feet_per_cubic_foot: 1,
+		report: false,
+	};
 
-		let volume = d.l*d.w*d.h;
-
-		total += volume + min_perim;
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match &arg[..] {
+			"--policy" => {
+				options.policy = match args.next() {
+					Some(policy) => Some(policy.clone()),
+					None => return Err("Expected a policy after --policy.".to_string()),
+				};
+			},
+			"--feet-per-cubic-foot" => {
+				options.feet_per_cubic_foot = match args.next().map(|value| value.parse::<u64>()) {
+					Some(Ok(feet)) => feet,
+					_ => return Err("Expected a number after --feet-per-cubic-foot.".to_string()),
+				};
+			},
+			"--report" => options.report = true,
+			_ => return Err(format!("Unknown option '{}'.", arg)),
+		};
 	}
 
-	total
+	return Ok(options);
+}
+
+fn get_policy(options: &Options) -> std::result::Result<Box<dyn WrappingPolicy>, String> {
+	match options.policy.as_ref().map(|policy| &policy[..]) {
+		None | Some("standard") => Ok(Box::new(StandardPolicy)),
+		Some("gift-bag") => Ok(Box::new(GiftBagPolicy)),
+		Some("volume-ribbon") => Ok(Box::new(VolumeRibbonPolicy {
+			feet_per_cubic_foot: options.feet_per_cubic_foot,
+		})),
+		Some(other) => Err(format!("Unknown policy '{}'.", other)),
+	}
 }
 
 fn main() {
     println!("Advent of Code - day 2");
 
+	let args: Vec<String> = env::args().skip(1).collect();
+	let (options, policy) = match parse_options(&args).and_then(|options| get_policy(&options).map(|policy| (options, policy))) {
+		Ok(parsed) => parsed,
+		Err(err) => {
+			eprintln!("{}\n{}", err, USAGE);
+			process::exit(2);
+		},
+	};
+
 	let input = read_file("input.txt").unwrap();
-	let dimensions = parse_dimensions(&input);
-	println!("Part 1 answer: {}", get_total_square_feet(&dimensions));
-	println!("Part 2 answer: {}", get_total_length(&dimensions));
+	let dimensions = match parse_dimensions(&input) {
+		Ok(dimensions) => dimensions,
+		Err(err) => {
+			eprintln!("{}", err);
+			process::exit(1);
+		},
+	};
+
+	let report = match PackingReport::new(&dimensions, &*policy) {
+		Ok(report) => report,
+		Err(err) => {
+			eprintln!("{}", err);
+			process::exit(1);
+		},
+	};
+
+	// Print every box, or the totals under another policy, instead of the
+	// puzzle answers.
+	if options.report {
+		println!("{}", report);
+		return;
+	}
+
+	if options.policy.is_some() {
+		println!("{} policy: paper {} ribbon {}", report.policy_name, report.total_paper, report.total_ribbon);
+		return;
+	}
+
+	println!("Part 1 answer: {}", report.total_paper);
+	println!("Part 2 answer: {}", report.total_ribbon);
 }
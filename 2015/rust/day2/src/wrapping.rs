@@ -0,0 +1,148 @@
+use std::fmt;
+use Dimensions;
+
+/// How much paper and ribbon a box needs. Both are None if the amount does
+/// not fit in a u64.
+pub trait WrappingPolicy {
+	fn name(&self) -> &str;
+
+	/// Square feet of wrapping paper.
+	fn paper(&self, d: &Dimensions) -> Option<u64>;
+
+	/// Feet of ribbon.
+	fn ribbon(&self, d: &Dimensions) -> Option<u64>;
+}
+
+/// Why a report could not be made. Boxes are numbered from 1, like the lines
+/// they were read from.
+#[derive(Debug)]
+pub enum PackingError {
+	PaperOverflow(usize),
+	RibbonOverflow(usize),
+	TotalPaperOverflow,
+	TotalRibbonOverflow,
+}
+
+impl fmt::Display for PackingError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			PackingError::PaperOverflow(number) => write!(f, "Box {} needs more paper than can be counted.", number),
+			PackingError::RibbonOverflow(number) => write!(f, "Box {} needs more ribbon than can be counted.", number),
+			PackingError::TotalPaperOverflow => write!(f, "The boxes need more paper in total than can be counted."),
+			PackingError::TotalRibbonOverflow => write!(f, "The boxes need more ribbon in total than can be counted."),
+		}
+	}
+}
+
+/// The elves' rules: the surface area plus the smallest side as slack, and
+/// the smallest perimeter plus the volume for the bow.
+pub struct StandardPolicy;
+
+/// A bag that covers the whole box, with the largest side as slack to close
+/// it, and only a bow for ribbon.
+pub struct GiftBagPolicy;
+
+/// The standard paper, with the ribbon for the bow priced by volume at the
+/// given feet per cubic foot.
+pub struct VolumeRibbonPolicy {
+	pub feet_per_cubic_foot: u64,
+}
+
+impl WrappingPolicy for StandardPolicy {
+	fn name(&self) -> &str {
+		"standard"
+	}
+
+	fn paper(&self, d: &Dimensions) -> Option<u64> {
+		d.surface_area().and_then(|area| area.checked_add(d.smallest_side()))
+	}
+
+	fn ribbon(&self, d: &Dimensions) -> Option<u64> {
+		d.volume().and_then(|volume| volume.checked_add(d.smallest_perimeter()))
+	}
+}
+
+impl WrappingPolicy for GiftBagPolicy {
+	fn name(&self) -> &str {
+		"gift bag"
+	}
+
+	fn paper(&self, d: &Dimensions) -> Option<u64> {
+		d.surface_area().and_then(|area| area.checked_add(d.largest_side()))
+	}
+
+	fn ribbon(&self, d: &Dimensions) -> Option<u64> {
+		d.volume()
+	}
+}
+
+impl WrappingPolicy for VolumeRibbonPolicy {
+	fn name(&self) -> &str {
+		"ribbon by volume"
+	}
+
+	fn paper(&self, d: &Dimensions) -> Option<u64> {
+		StandardPolicy.paper(d)
+	}
+
+	fn ribbon(&self, d: &Dimensions) -> Option<u64> {
+		d.volume()
+			.and_then(|volume| volume.checked_mul(self.feet_per_cubic_foot))
+			.and_then(|bow| bow.checked_add(d.smallest_perimeter()))
+	}
+}
+
+pub struct BoxReport<'a> {
+	pub dimensions: &'a Dimensions,
+	pub paper: u64,
+	pub ribbon: u64,
+}
+
+/// The paper and ribbon for every box under one policy.
+pub struct PackingReport<'a> {
+	pub policy_name: String,
+	pub boxes: Vec<BoxReport<'a>>,
+	pub total_paper: u64,
+	pub total_ribbon: u64,
+}
+
+impl<'a> PackingReport<'a> {
+	pub fn new<P: WrappingPolicy + ?Sized>(dimensions: &'a Vec<Dimensions>, policy: &P) -> Result<PackingReport<'a>, PackingError> {
+		let mut boxes: Vec<BoxReport> = Vec::with_capacity(dimensions.len());
+		let mut total_paper: u64 = 0;
+		let mut total_ribbon: u64 = 0;
+
+		for (index, d) in dimensions.iter().enumerate() {
+			let paper = policy.paper(d).ok_or(PackingError::PaperOverflow(index + 1))?;
+			let ribbon = policy.ribbon(d).ok_or(PackingError::RibbonOverflow(index + 1))?;
+
+			total_paper = total_paper.checked_add(paper).ok_or(PackingError::TotalPaperOverflow)?;
+			total_ribbon = total_ribbon.checked_add(ribbon).ok_or(PackingError::TotalRibbonOverflow)?;
+
+			boxes.push(BoxReport {
+				dimensions: d,
+				paper: paper,
+				ribbon: ribbon,
+			});
+		}
+
+		Ok(PackingReport {
+			policy_name: policy.name().to_string(),
+			boxes: boxes,
+			total_paper: total_paper,
+			total_ribbon: total_ribbon,
+		})
+	}
+}
+
+impl<'a> fmt::Display for PackingReport<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "{} policy", self.policy_name)?;
+
+		for (index, b) in self.boxes.iter().enumerate() {
+			writeln!(f, "{:>5}: {}x{}x{} paper {} ribbon {}", index + 1, b.dimensions.l, b.dimensions.w, b.dimensions.h, b.paper, b.ribbon)?;
+		}
+
+		write!(f, "total: paper {} ribbon {}", self.total_paper, self.total_ribbon)
+	}
+}
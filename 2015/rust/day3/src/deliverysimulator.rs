@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use increment_key;
+use move_position;
+
+/// Characters used to draw a house, from the fewest visits to the most.
+const HEAT_LEVELS: [char; 10] = [' ', '.', ':', '-', '=', '+', '*', '#', '%', '@'];
+
+/// Delivers presents with several agents that share one list of moves. Each
+/// move is given to an agent, which walks from wherever it last stopped.
+pub struct DeliverySimulator {
+	agent_count: usize,
+	assignment: Box<dyn Fn(usize) -> usize>,
+}
+
+/// The visits to each house, for each agent and for all of them together.
+pub struct Delivery {
+	pub agents: Vec<HashMap<(i32, i32), i32>>,
+	pub combined: HashMap<(i32, i32), i32>,
+}
+
+impl DeliverySimulator {
+	/// The agents take turns, one move each.
+	pub fn round_robin(agent_count: usize) -> DeliverySimulator {
+		DeliverySimulator::with_assignment(agent_count, move |index| index.checked_rem(agent_count).unwrap_or(0))
+	}
+
+	/// The function picks the agent for the move with the given index, where
+	/// characters that are not moves are not counted.
+	pub fn with_assignment<F>(agent_count: usize, assignment: F) -> DeliverySimulator
+		where F : Fn(usize) -> usize + 'static {
+
+		DeliverySimulator {
+			agent_count: agent_count,
+			assignment: Box::new(assignment),
+		}
+	}
+
+	pub fn run(&self, input: &str) -> Result<Delivery, String> {
+		let mut agents = vec![HashMap::new(); self.agent_count];
+		let mut positions = vec![(0, 0); self.agent_count];
+
+		for matrix in agents.iter_mut() {
+			increment_key((0, 0), matrix);
+		}
+
+		for (index, c) in input.chars().filter(|&c| move_position((0, 0), c).is_some()).enumerate() {
+			let agent = (self.assignment)(index);
+			if agent >= self.agent_count {
+				return Err(format!("Move {} was given to agent {} but there are only {} agents.", index, agent, self.agent_count));
+			}
+
+			positions[agent] = move_position(positions[agent], c).unwrap();
+			increment_key(positions[agent], &mut agents[agent]);
+		}
+
+		let mut combined = HashMap::new();
+		for matrix in &agents {
+			for (position, count) in matrix {
+				*combined.entry(*position).or_insert(0) += *count;
+			}
+		}
+
+		return Ok(Delivery {
+			agents: agents,
+			combined: combined,
+		});
+	}
+}
+
+impl Delivery {
+	/// The houses that every agent visited at least once, from top to bottom
+	/// and left to right.
+	pub fn houses_visited_by_all(&self) -> Vec<(i32, i32)> {
+		let mut houses: Vec<(i32, i32)> = self.combined
+			.keys()
+			.filter(|position| self.agents.iter().all(|matrix| matrix.contains_key(position)))
+			.cloned()
+			.collect();
+
+		houses.sort();
+		return houses;
+	}
+}
+
+/// Draws the visits to each house, one row per line, with busier houses in
+/// denser characters.
+pub fn render_heatmap(matrix: &HashMap<(i32, i32), i32>) -> String {
+	let mut output = String::new();
+
+	if matrix.is_empty() {
+		return output;
+	}
+
+	let min_row = matrix.keys().map(|p| p.0).min().unwrap();
+	let max_row = matrix.keys().map(|p| p.0).max().unwrap();
+	let min_col = matrix.keys().map(|p| p.1).min().unwrap();
+	let max_col = matrix.keys().map(|p| p.1).max().unwrap();
+	let max_count = *matrix.values().max().unwrap() as i64;
+
+	for row in min_row..max_row + 1 {
+		for col in min_col..max_col + 1 {
+			let count = *matrix.get(&(row, col)).unwrap_or(&0) as i64;
+			let top = (HEAT_LEVELS.len() - 1) as i64;
+			let level = match count {
+				0 => 0,
+				_ => (count * top + max_count - 1) / max_count,
+			};
+
+			output.push(HEAT_LEVELS[level as usize]);
+		}
+
+		output.push('\n');
+	}
+
+	return output;
+}
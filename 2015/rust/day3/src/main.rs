@@ -1,7 +1,14 @@
+use std::env;
 use std::fs::File;
 use std::io::Result;
 use std::io::Read;
 use std::collections::HashMap;
+use std::process;
+
+mod deliverysimulator;
+
+use deliverysimulator::DeliverySimulator;
+use deliverysimulator::render_heatmap;
 
 fn read_file(path: &str) -> Result<String> {
     let mut file = try!(File::open(path));
@@ -27,60 +34,79 @@ fn move_position(position: (i32, i32), c: char) -> Option<(i32, i32)> {
 	}
 }
 
-fn build_matrix_part_1(input: &str) -> HashMap<(i32, i32), i32> {
-	let mut matrix = HashMap::new();
-	let mut position = (0, 0);
-	increment_key(position, &mut matrix);
+const USAGE: &'static str = "usage: day3 [--agents COUNT] [--heatmap]";
 
-	for c in input.chars() {
-		let new_position = move_position(position, c);
+struct Options {
+	agents: Option<usize>,
+	heatmap: bool,
+}
 
-		if new_position.is_some() {
-			position = new_position.unwrap();
-			increment_key(position, &mut matrix);
-		}
+fn parse_options(args: &[String]) -> std::result::Result<Options, String> {
+	let mut options = Options {
+		agents: None,
+		heatmap: false,
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match &arg[..] {
+			"--agents" => {
+				options.agents = match args.next().map(|value| value.parse::<usize>()) {
+					Some(Ok(count)) if count > 0 => Some(count),
+					_ => return Err("Expected a number of agents after --agents.".to_string()),
+				};
+			},
+			"--heatmap" => options.heatmap = true,
+			_ => return Err(format!("Unknown option '{}'.", arg)),
+		};
 	}
 
-	return matrix;
+	return Ok(options);
 }
 
+/// Prints the houses each agent visited and the houses they all visited,
+/// followed by the heatmap of every visit if it was asked for.
+fn print_delivery(input: &str, agent_count: usize, heatmap: bool) -> std::result::Result<(), String> {
+	let delivery = DeliverySimulator::round_robin(agent_count).run(input)?;
 
-fn build_matrix_part_2(input: &str) -> HashMap<(i32, i32), i32> {
-	let mut matrix = HashMap::new();
-	let mut position_a = (0, 0);
-	let mut position_b = (0, 0);
-	increment_key(position_a, &mut matrix);
-	increment_key(position_b, &mut matrix);
-	let mut next_is_a = true;
-
-	for c in input.chars() {
-		let position = match next_is_a {
-			true => position_a,
-			false => position_b,
-		};
-
-		let new_position = move_position(position, c);
+	for (agent, matrix) in delivery.agents.iter().enumerate() {
+		println!("Agent {} visited {} houses", agent + 1, matrix.len());
+	}
 
-		if new_position.is_some() {
+	println!("The agents visited {} houses together", delivery.combined.len());
+	println!("Every agent visited {} of the same houses", delivery.houses_visited_by_all().len());
 
-			if next_is_a {
-				position_a = new_position.unwrap();
-			} else {
-				position_b = new_position.unwrap();
-			}
-			
-			increment_key(new_position.unwrap(), &mut matrix);
-			next_is_a = !next_is_a;
-		}
+	if heatmap {
+		print!("{}", render_heatmap(&delivery.combined));
 	}
 
-	return matrix;
+	return Ok(());
 }
 
 fn main() {
     println!("Advent of Code - day 3");
 
+	let args: Vec<String> = env::args().skip(1).collect();
+	let options = match parse_options(&args) {
+		Ok(options) => options,
+		Err(err) => {
+			eprintln!("{}\n{}", err, USAGE);
+			process::exit(2);
+		},
+	};
+
 	let input = read_file("input.txt").unwrap();
-	println!("Part 1 answer: {}", build_matrix_part_1(&input).len());
-	println!("Part 2 answer: {}", build_matrix_part_2(&input).len());
+
+	// Simulate any number of agents instead of solving the puzzle.
+	if options.agents.is_some() || options.heatmap {
+		if let Err(err) = print_delivery(&input, options.agents.unwrap_or(1), options.heatmap) {
+			eprintln!("{}", err);
+			process::exit(1);
+		}
+
+		return;
+	}
+
+	println!("Part 1 answer: {}", DeliverySimulator::round_robin(1).run(&input).unwrap().combined.len());
+	println!("Part 2 answer: {}", DeliverySimulator::round_robin(2).run(&input).unwrap().combined.len());
 }
@@ -1,6 +1,12 @@
+use std::env;
 use std::fs::File;
 use std::io::Result;
 use std::io::Read;
+use std::process;
+
+mod niceness;
+
+use niceness::Policy;
 
 fn read_file(path: &str) -> Result<String> {
     let mut file = try!(File::open(path));
@@ -9,115 +15,98 @@ fn read_file(path: &str) -> Result<String> {
     Ok(content)
 }
 
-fn has_3_vowels(input: &str) -> bool {
-	let mut vowel_count = 0;
-	for c in input.chars() {
-		let delta = match c {
-			'a' | 'e' | 'i' | 'o' | 'u' => 1,
-			_ => 0,
-		};
-		vowel_count += delta;
-
-		if vowel_count >= 3 {
-			return true;
-		}
-	}
-
-	return false;
-}
-
-fn has_double_letter(input: &str) -> bool {
-	let mut last_letter = None;
-	for c in input.chars() {
-		if last_letter.is_some() && c == last_letter.unwrap() {
-			return true;
-		}
-
-		last_letter = Some(c);
-	}
-
-	return false;
-}
-
-fn has_repeated_pair(input: &str) -> bool {
-	let mut chars = input.chars();
-	let mut last_letter = chars.next().unwrap();
-
-	for c in chars {
-		let pair: String = vec![last_letter, c].into_iter().collect();
-		last_letter = c;
-
-		let first = input.find(&pair[..]);
-		if first.is_none() {
-			continue;
-		}
-
-		let second = input[first.unwrap() + 2..].find(&pair[..]);
-		if second.is_none() {
-			continue;
+fn count_nice(input: &str, policy: &Policy) -> i32 {
+	let mut count = 0;
+	for line in input.lines() {
+		if policy.is_nice(line) {
+			count += 1;
 		}
-
-		return true;
 	}
 
-	return false;
+	return count;
 }
 
-fn has_sandwiched_char(input: &str) -> bool {
-	let chars: Vec<char> = input.chars().collect();
-	for i in 0..chars.len() - 2 {
-		if chars[i] == chars[i + 2] {
-			return true;
-		}
-	}
-
-	return false;
+/// The puzzle's first policy, read from part1.rules when building.
+fn part_1_policy() -> Policy {
+	return Policy::parse(include_str!("part1.rules")).unwrap();
 }
 
-fn has_no_naughty(input: &str) -> bool {
-	return !input.contains("ab") &&
-	       !input.contains("cd") &&
-	       !input.contains("pq") &&
-	       !input.contains("xy");
+/// The puzzle's second policy, read from part2.rules when building.
+fn part_2_policy() -> Policy {
+	return Policy::parse(include_str!("part2.rules")).unwrap();
 }
 
-fn is_nice_part_1(input: &str) -> bool {
-	return has_3_vowels(input) &&
-	       has_double_letter(input) &&
-	       has_no_naughty(input);
-}
+const USAGE: &'static str = "usage: day5 [--rules PATH] [--explain STRING]...";
 
-fn is_nice_part_2(input: &str) -> bool {
-	return has_repeated_pair(input) &&
-	       has_sandwiched_char(input);
+struct Options {
+	rules: Option<String>,
+	explain: Vec<String>,
 }
 
-fn count_nice_part_1(input: &str) -> i32 {
-	let mut count = 0;
-	for line in input.lines() {
-		if is_nice_part_1(line) {
-			count += 1;
-		}
-	}
-
-	return count;
-}
+fn parse_options(args: &[String]) -> std::result::Result<Options, String> {
+	let mut options = Options {
+		rules: None,
+		explain: Vec::new(),
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		let value = match args.next() {
+			Some(value) => value.clone(),
+			None => return Err(format!("Expected a value after {}.", arg)),
+		};
 
-fn count_nice_part_2(input: &str) -> i32 {
-	let mut count = 0;
-	for line in input.lines() {
-		if is_nice_part_2(line) {
-			count += 1;
-		}
+		match &arg[..] {
+			"--rules" => options.rules = Some(value),
+			"--explain" => options.explain.push(value),
+			_ => return Err(format!("Unknown option '{}'.", arg)),
+		};
 	}
 
-	return count;
+	return Ok(options);
 }
 
 fn main() {
     println!("Advent of Code - day 5");
 
+	let args: Vec<String> = env::args().skip(1).collect();
+	let options = match parse_options(&args) {
+		Ok(options) => options,
+		Err(err) => {
+			eprintln!("{}\n{}", err, USAGE);
+			process::exit(2);
+		},
+	};
+
+	// With a policy file, count and explain with that policy instead of the
+	// puzzle's two.
+	let policies = match options.rules {
+		Some(ref path) => {
+			let policy = read_file(path).map_err(|err| err.to_string()).and_then(|rules| Policy::parse(&rules));
+			match policy {
+				Ok(policy) => vec![("Custom", policy)],
+				Err(err) => {
+					eprintln!("{}", err);
+					process::exit(1);
+				},
+			}
+		},
+		None => vec![("Part 1", part_1_policy()), ("Part 2", part_2_policy())],
+	};
+
+	if !options.explain.is_empty() {
+		for &(name, ref policy) in &policies {
+			println!("{} policy:", name);
+			for input in &options.explain {
+				print!("{}", policy.explain(input));
+			}
+		}
+
+		return;
+	}
+
 	let input = read_file("input.txt").unwrap();
-	println!("Part 1 answer: {}", count_nice_part_1(&input));
-	println!("Part 2 answer: {}", count_nice_part_2(&input));
+	for &(name, ref policy) in &policies {
+		println!("{} answer: {}", name, count_nice(&input, policy));
+	}
 }
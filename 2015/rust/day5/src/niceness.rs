@@ -0,0 +1,210 @@
+use std::fmt;
+
+/// One test a string has to pass to be nice.
+pub enum Rule {
+	/// At least `count` of the string's characters are in `chars`.
+	CountedClass { chars: String, count: usize },
+	/// None of the substrings appear in the string. The explanation gives the
+	/// one that appears first.
+	Forbidden(Vec<String>),
+	/// Some run of `length` characters appears twice without overlapping.
+	RepeatedNgram { length: usize },
+	/// Some character appears again with exactly `gap` characters between.
+	GappedRepeat { gap: usize },
+}
+
+/// Whether a string passed a rule, and why.
+pub struct RuleResult {
+	pub passed: bool,
+	pub detail: String,
+}
+
+/// A string is nice if it passes every rule of the policy.
+pub struct Policy {
+	rules: Vec<Rule>,
+}
+
+pub struct Explanation<'a> {
+	pub input: String,
+	pub results: Vec<(&'a Rule, RuleResult)>,
+}
+
+impl Rule {
+	/// Parses a line of a policy file, such as "count 3 aeiou",
+	/// "forbid ab cd", "repeated-ngram 2" or "gapped-repeat 1".
+	pub fn parse(line: &str) -> Result<Rule, String> {
+		let pieces: Vec<&str> = line.split_whitespace().collect();
+
+		match (pieces.get(0).cloned().unwrap_or(""), pieces.len()) {
+			("count", 3)           => Ok(Rule::CountedClass {
+				chars: pieces[2].to_string(),
+				count: parse_number(pieces[1])?,
+			}),
+			("forbid", n) if n > 1 => Ok(Rule::Forbidden(pieces[1..].iter().map(|s| s.to_string()).collect())),
+			("repeated-ngram", 2)  => Ok(Rule::RepeatedNgram {
+				length: parse_number(pieces[1])?,
+			}),
+			("gapped-repeat", 2)   => Ok(Rule::GappedRepeat {
+				gap: parse_number(pieces[1])?,
+			}),
+			_                      => Err(format!("The rule '{}' is not valid.", line)),
+		}
+	}
+
+	pub fn check(&self, input: &str) -> RuleResult {
+		let input_chars: Vec<char> = input.chars().collect();
+
+		match *self {
+			Rule::CountedClass { ref chars, count } => {
+				let found = input.chars().filter(|c| chars.contains(*c)).count();
+				RuleResult {
+					passed: found >= count,
+					detail: format!("found {} of {}", found, count),
+				}
+			},
+			Rule::Forbidden(ref substrings) => {
+				let earliest = substrings
+					.iter()
+					.enumerate()
+					.filter_map(|(order, s)| find_substring(&input_chars, s).map(|i| (i, order, s)))
+					.min();
+
+				match earliest.map(|(i, _, s)| (s, i)) {
+					Some((s, i)) => RuleResult {
+						passed: false,
+						detail: format!("{} at {}", s, i),
+					},
+					None         => RuleResult {
+						passed: true,
+						detail: "none found".to_string(),
+					},
+				}
+			},
+			Rule::RepeatedNgram { length } => {
+				match find_repeated_ngram(&input_chars, length) {
+					Some((first, second)) => RuleResult {
+						passed: true,
+						detail: format!("{} at {} and {}", input_chars[first..first + length].iter().cloned().collect::<String>(), first, second),
+					},
+					None                  => RuleResult {
+						passed: false,
+						detail: "none found".to_string(),
+					},
+				}
+			},
+			Rule::GappedRepeat { gap } => {
+				match (0..input_chars.len()).find(|&i| i + gap + 1 < input_chars.len() && input_chars[i] == input_chars[i + gap + 1]) {
+					Some(i) => RuleResult {
+						passed: true,
+						detail: format!("{} at {} and {}", input_chars[i], i, i + gap + 1),
+					},
+					None    => RuleResult {
+						passed: false,
+						detail: "none found".to_string(),
+					},
+				}
+			},
+		}
+	}
+}
+
+impl fmt::Display for Rule {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Rule::CountedClass { ref chars, count } => write!(f, "count {} {}", count, chars),
+			Rule::Forbidden(ref substrings)         => write!(f, "forbid {}", substrings.join(" ")),
+			Rule::RepeatedNgram { length }          => write!(f, "repeated-ngram {}", length),
+			Rule::GappedRepeat { gap }              => write!(f, "gapped-repeat {}", gap),
+		}
+	}
+}
+
+impl Policy {
+	pub fn new() -> Policy {
+		Policy {
+			rules: Vec::new(),
+		}
+	}
+
+	/// Parses a policy with one rule per line. Blank lines and lines starting
+	/// with '#' are skipped.
+	pub fn parse(input: &str) -> Result<Policy, String> {
+		let mut policy = Policy::new();
+
+		for line in input.lines() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			policy.rules.push(Rule::parse(line)?);
+		}
+
+		return Ok(policy);
+	}
+
+	pub fn is_nice(&self, input: &str) -> bool {
+		return self.rules.iter().all(|rule| rule.check(input).passed);
+	}
+
+	pub fn explain<'a>(&'a self, input: &str) -> Explanation<'a> {
+		Explanation {
+			input: input.to_string(),
+			results: self.rules.iter().map(|rule| (rule, rule.check(input))).collect(),
+		}
+	}
+}
+
+impl<'a> Explanation<'a> {
+	pub fn is_nice(&self) -> bool {
+		return self.results.iter().all(|&(_, ref result)| result.passed);
+	}
+}
+
+impl<'a> fmt::Display for Explanation<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "{} is {}", self.input, if self.is_nice() { "nice" } else { "naughty" })?;
+
+		for &(rule, ref result) in &self.results {
+			writeln!(f, "  {} {}: {}", if result.passed { "pass" } else { "fail" }, rule, result.detail)?;
+		}
+
+		Ok(())
+	}
+}
+
+fn parse_number(input: &str) -> Result<usize, String> {
+	match input.parse::<usize>() {
+		Ok(number) => Ok(number),
+		Err(_)     => Err(format!("{} is not a number.", input)),
+	}
+}
+
+/// The character position of the first appearance of the substring.
+fn find_substring(chars: &Vec<char>, substring: &str) -> Option<usize> {
+	let substring: Vec<char> = substring.chars().collect();
+	if substring.is_empty() || chars.len() < substring.len() {
+		return None;
+	}
+
+	return (0..chars.len() - substring.len() + 1).find(|&i| chars[i..i + substring.len()] == substring[..]);
+}
+
+/// The positions of the first run of characters that appears again later
+/// without overlapping, and of where it appears again.
+fn find_repeated_ngram(chars: &Vec<char>, length: usize) -> Option<(usize, usize)> {
+	if length == 0 || chars.len() < length * 2 {
+		return None;
+	}
+
+	for first in 0..chars.len() - length * 2 + 1 {
+		let ngram = &chars[first..first + length];
+		for second in first + length..chars.len() - length + 1 {
+			if &chars[second..second + length] == ngram {
+				return Some((first, second));
+			}
+		}
+	}
+
+	return None;
+}
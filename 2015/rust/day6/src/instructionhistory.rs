@@ -0,0 +1,104 @@
+use std::cmp::max;
+use std::cmp::min;
+use Instruction;
+use lightrule::LightRule;
+use sparsegrid::SparseGrid;
+
+/// How many instructions are run between two snapshots when no interval is
+/// given.
+pub const DEFAULT_SNAPSHOT_INTERVAL: usize = 16;
+
+/// Answers questions about a region of the grid after any number of
+/// instructions. The blocks of the grid are saved every few instructions, so
+/// a question only replays the instructions since the last snapshot, and only
+/// on the blocks inside the region.
+pub struct InstructionHistory<R: LightRule> {
+	rule: R,
+	instructions: Vec<Instruction>,
+	grid: SparseGrid<R::Value>,
+	snapshots: Vec<Vec<R::Value>>,
+	snapshot_interval: usize,
+}
+
+impl<R: LightRule> InstructionHistory<R> {
+	pub fn new(width: usize, height: usize, instructions: Vec<Instruction>, rule: R) -> Result<InstructionHistory<R>, &'static str> {
+		InstructionHistory::with_snapshot_interval(width, height, instructions, rule, DEFAULT_SNAPSHOT_INTERVAL)
+	}
+
+	pub fn with_snapshot_interval(width: usize, height: usize, instructions: Vec<Instruction>, rule: R, snapshot_interval: usize) -> Result<InstructionHistory<R>, &'static str> {
+		if snapshot_interval == 0 {
+			return Err("The snapshot interval must be at least one.");
+		}
+
+		let mut grid = SparseGrid::new(width, height, &instructions, rule.initial())?;
+		let mut snapshots = vec![grid.blocks().to_vec()];
+
+		for (index, instruction) in instructions.iter().enumerate() {
+			grid.apply(instruction, |instruction_type, old_value| rule.apply(instruction_type, old_value));
+
+			if (index + 1) % snapshot_interval == 0 {
+				snapshots.push(grid.blocks().to_vec());
+			}
+		}
+
+		return Ok(InstructionHistory {
+			rule: rule,
+			instructions: instructions,
+			grid: grid,
+			snapshots: snapshots,
+			snapshot_interval: snapshot_interval,
+		});
+	}
+
+	pub fn len(&self) -> usize {
+		self.instructions.len()
+	}
+
+	/// The total brightness of the lights in the rectangle after the first
+	/// `step` instructions. For the on/off rule, this is the number of lit
+	/// lights.
+	pub fn total_at(&self, step: usize, upper_left: (i32, i32), lower_right: (i32, i32)) -> Result<u64, &'static str> {
+		if step > self.len() {
+			return Err("The step is after the last instruction.");
+		}
+
+		if !self.grid.contains(upper_left, lower_right) {
+			return Err("The region is outside of the grid.");
+		}
+
+		// copy the region's blocks out of the last snapshot before the step
+		let checkpoint = step / self.snapshot_interval;
+		let snapshot = &self.snapshots[checkpoint];
+		let columns = self.grid.columns();
+		let (x_range, y_range) = self.grid.block_ranges(upper_left, lower_right);
+		let region_columns = x_range.len();
+
+		let mut values = Vec::with_capacity(x_range.len() * y_range.len());
+		for y in y_range.clone() {
+			for x in x_range.clone() {
+				values.push(snapshot[y * columns + x]);
+			}
+		}
+
+		// replay the instructions since the snapshot on the region only
+		for instruction in &self.instructions[checkpoint * self.snapshot_interval..step] {
+			let (instruction_x_range, instruction_y_range) = self.grid.block_ranges(instruction.upper_left, instruction.lower_right);
+
+			for y in max(y_range.start, instruction_y_range.start)..min(y_range.end, instruction_y_range.end) {
+				for x in max(x_range.start, instruction_x_range.start)..min(x_range.end, instruction_x_range.end) {
+					let index = (y - y_range.start) * region_columns + (x - x_range.start);
+					values[index] = self.rule.apply(&instruction.instruction_type, values[index]);
+				}
+			}
+		}
+
+		let mut total = 0;
+		for (index, value) in values.into_iter().enumerate() {
+			let x = x_range.start + index % region_columns;
+			let y = y_range.start + index / region_columns;
+			total += self.rule.brightness(value) * self.grid.overlap(x, y, upper_left, lower_right);
+		}
+
+		return Ok(total);
+	}
+}
@@ -0,0 +1,65 @@
+use std::cmp::max;
+use InstructionType;
+
+/// Decides what an instruction does to a single light.
+pub trait LightRule {
+	type Value: Copy;
+
+	/// The value of every light before any instruction.
+	fn initial(&self) -> Self::Value;
+
+	fn apply(&self, instruction_type: &InstructionType, old_value: Self::Value) -> Self::Value;
+
+	/// How much a light with this value counts towards the answer.
+	fn brightness(&self, value: Self::Value) -> u64;
+}
+
+/// Part 1: lights are either on or off.
+#[derive(Clone, Copy)]
+pub struct OnOffRule;
+
+impl LightRule for OnOffRule {
+	type Value = bool;
+
+	fn initial(&self) -> bool {
+		false
+	}
+
+	fn apply(&self, instruction_type: &InstructionType, old_value: bool) -> bool {
+		match *instruction_type {
+			InstructionType::TurnOn => true,
+			InstructionType::Toggle => !old_value,
+			InstructionType::TurnOff => false,
+		}
+	}
+
+	fn brightness(&self, value: bool) -> u64 {
+		if value { 1 } else { 0 }
+	}
+}
+
+/// Part 2: lights have a brightness that never goes below zero.
+#[derive(Clone, Copy)]
+pub struct BrightnessRule;
+
+impl LightRule for BrightnessRule {
+	type Value = i32;
+
+	fn initial(&self) -> i32 {
+		0
+	}
+
+	fn apply(&self, instruction_type: &InstructionType, old_value: i32) -> i32 {
+		let new_value = match *instruction_type {
+			InstructionType::TurnOn => old_value + 1,
+			InstructionType::Toggle => old_value + 2,
+			InstructionType::TurnOff => old_value - 1,
+		};
+
+		max(0, new_value)
+	}
+
+	fn brightness(&self, value: i32) -> u64 {
+		value as u64
+	}
+}
@@ -1,14 +1,31 @@
+use std::env;
 use std::fs::File;
 use std::io;
 use std::io::Read;
+use std::process;
 use std::result::Result;
 use std::cmp::min;
 use std::cmp::max;
 use std::fmt;
 use std::fmt::Display;
 
-extern crate bit_vec;
-use bit_vec::BitVec;
+#[cfg(feature = "png")]
+extern crate png;
+
+mod image;
+use image::BitmapImage;
+use image::GrayImage;
+use instructionhistory::InstructionHistory;
+
+mod instructionhistory;
+mod lightrule;
+mod sparsegrid;
+#[cfg(test)]
+mod tests;
+use lightrule::BrightnessRule;
+use lightrule::LightRule;
+use lightrule::OnOffRule;
+use sparsegrid::SparseGrid;
 
 
 fn read_file(path: &str) -> io::Result<String> {
@@ -102,6 +119,17 @@ impl Instruction {
 		});
 	}
 
+	fn check_bounds(&self, width: usize, height: usize) -> Result<(), &'static str> {
+		if self.upper_left.0 < 0
+			|| self.upper_left.1 < 0
+			|| self.lower_right.0 as usize >= width
+			|| self.lower_right.1 as usize >= height {
+			return Err("The instruction is outside of the grid.");
+		}
+
+		return Ok(());
+	}
+
 	fn parse_coordinate(input: &str) -> Result<(i32, i32), &str> {
 		let pieces: Vec<&str> = input.split(',').collect();
 		if pieces.len() != 2 {
@@ -119,101 +147,156 @@ impl Instruction {
 	}
 }
 
-fn print_grid(grid: &BitVec, width: usize, height: usize) -> () {
-	for x in 0..width {
-		for y in 0..height {
-			let index = y * width + x;
-			if grid.get(index).unwrap() {
-				print!("1");
-			} else {
-				print!("0");
-			}
-		}
-		println!("");
+/// Parses every instruction, skipping lines that are not valid instructions.
+fn parse_instructions(input: &str) -> Vec<Instruction> {
+	let mut instructions = Vec::new();
+	for line in input.lines() {
+		match Instruction::parse(line) {
+			Ok(instruction) => instructions.push(instruction),
+			Err(_) => continue,
+		};
 	}
 
-	println!("");
+	return instructions;
 }
 
-fn process_instructions_part_1(input: &str, width: usize, height: usize) -> i32 {
-	// initialize the grid
-	let mut grid = BitVec::from_elem(width * height, false);
+fn save_grid_image(grid: &SparseGrid<bool>, width: usize, height: usize, path: &str) -> io::Result<()> {
+	BitmapImage::from_fn(width, height, |x, y| grid.get(x, y)).save(path)
+}
 
-	// process the instructions
-	for line in input.lines() {
-		let instruction_option = Instruction::parse(line);
-		if instruction_option.is_err() {
-			continue;
+/// Saves the brightness of every light, scaled so that the brightest light is
+/// white.
+fn save_brightness_image(grid: &SparseGrid<i32>, width: usize, height: usize, path: &str) -> io::Result<()> {
+	let mut values = Vec::with_capacity(width * height);
+	for y in 0..height {
+		for x in 0..width {
+			values.push(grid.get(x, y));
 		}
+	}
 
-		let instruction = instruction_option.unwrap();
-
-		for x in instruction.upper_left.0..instruction.lower_right.0 + 1 {
-			for y in instruction.upper_left.1..instruction.lower_right.1 + 1 {
-				let index = ((y as usize) * width) + (x as usize);
-				let old_value = grid.get(index).unwrap();
-				let new_value = match instruction.instruction_type {
-					InstructionType::TurnOn => true,
-					InstructionType::Toggle => !old_value,
-					InstructionType::TurnOff => false,
-				};
+	GrayImage::from_intensities(width, height, &values).save(path)
+}
 
-				grid.set(index as usize, new_value);
-			}
-		}
-	}
+fn process_instructions<R: LightRule>(input: &str, width: usize, height: usize, rule: &R) -> Result<SparseGrid<R::Value>, &'static str> {
+	let instructions = parse_instructions(input);
+	let mut grid = SparseGrid::new(width, height, &instructions, rule.initial())?;
 
-	// count true 
-	let mut count = 0;
-	for value in grid {
-		if value {
-			count += 1;
-		}
+	for instruction in &instructions {
+		grid.apply(instruction, |instruction_type, old_value| rule.apply(instruction_type, old_value));
 	}
 
-	return count;
+	return Ok(grid);
 }
 
-fn process_instructions_part_2(input: &str, width: usize, height: usize) -> i32 {
-	// initialize the grid
-	let mut grid: Vec<i32> = vec![0; width * height];
+/// Prints how many lights are lit, and how bright they are, inside a region
+/// after the first `step` instructions, or after all of them.
+fn print_region(input: &str, upper_left: (i32, i32), lower_right: (i32, i32), step: Option<usize>) -> Result<(), &'static str> {
+	let lit = InstructionHistory::new(1000, 1000, parse_instructions(input), OnOffRule)?;
+	let brightness = InstructionHistory::new(1000, 1000, parse_instructions(input), BrightnessRule)?;
+	let step = step.unwrap_or(lit.len());
+
+	println!(
+		"After {} of {} instructions, {},{} through {},{} has {} lights lit and a total brightness of {}.",
+		step,
+		lit.len(),
+		upper_left.0,
+		upper_left.1,
+		lower_right.0,
+		lower_right.1,
+		lit.total_at(step, upper_left, lower_right)?,
+		brightness.total_at(step, upper_left, lower_right)?);
+
+	return Ok(());
+}
 
-	// process the instructions
-	for line in input.lines() {
-		let instruction_option = Instruction::parse(line);
-		if instruction_option.is_err() {
-			continue;
-		}
+const USAGE: &'static str = "usage: day6 [--image PATH] [--brightness-image PATH] [--from X,Y --to X,Y [--step N]]";
 
-		let instruction = instruction_option.unwrap();
+struct Options {
+	image: Option<String>,
+	brightness_image: Option<String>,
+	from: Option<(i32, i32)>,
+	to: Option<(i32, i32)>,
+	step: Option<usize>,
+}
 
-		for x in instruction.upper_left.0..instruction.lower_right.0 + 1 {
-			for y in instruction.upper_left.1..instruction.lower_right.1 + 1 {
-				let index = ((y as usize) * width) + (x as usize);
-				let old_value = grid[index];
-				let new_value = match instruction.instruction_type {
-					InstructionType::TurnOn => old_value + 1,
-					InstructionType::Toggle => old_value + 2,
-					InstructionType::TurnOff => old_value - 1,
+fn parse_options(args: &[String]) -> Result<Options, String> {
+	let mut options = Options {
+		image: None,
+		brightness_image: None,
+		from: None,
+		to: None,
+		step: None,
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		let value = match args.next() {
+			Some(value) => value.clone(),
+			None => return Err(format!("Expected a value after {}.", arg)),
+		};
+
+		match &arg[..] {
+			"--image" => options.image = Some(value),
+			"--brightness-image" => options.brightness_image = Some(value),
+			"--from" => options.from = Some(Instruction::parse_coordinate(&value).map_err(|err| err.to_string())?),
+			"--to" => options.to = Some(Instruction::parse_coordinate(&value).map_err(|err| err.to_string())?),
+			"--step" => {
+				options.step = match value.parse::<usize>() {
+					Ok(step) => Some(step),
+					Err(_) => return Err(format!("Expected a number of instructions after {}.", arg)),
 				};
+			},
+			_ => return Err(format!("Unknown option '{}'.", arg)),
+		};
+	}
 
-				grid[index] = max(0, new_value); 
-			}
-		}
+	if options.from.is_some() != options.to.is_some() {
+		return Err("A region needs both --from and --to.".to_string());
 	}
 
-	let mut total = 0;
-	for value in grid {
-		total += value;
+	if options.step.is_some() && options.from.is_none() {
+		return Err("--step needs a region to look at.".to_string());
 	}
 
-	return total;
+	return Ok(options);
 }
 
 fn main() {
     println!("Advent of Code - day 6");
 
+	let args: Vec<String> = env::args().skip(1).collect();
+	let options = match parse_options(&args) {
+		Ok(options) => options,
+		Err(err) => {
+			eprintln!("{}\n{}", err, USAGE);
+			process::exit(2);
+		},
+	};
+
 	let input = read_file("input.txt").unwrap();
-	println!("Part 1 answer: {}", process_instructions_part_1(&input, 1000, 1000));
-	println!("Part 2 answer: {}", process_instructions_part_2(&input, 1000, 1000));
+
+	// Look at one region of the grid instead of solving the puzzle.
+	if let (Some(from), Some(to)) = (options.from, options.to) {
+		let upper_left = (min(from.0, to.0), min(from.1, to.1));
+		let lower_right = (max(from.0, to.0), max(from.1, to.1));
+
+		if let Err(err) = print_region(&input, upper_left, lower_right, options.step) {
+			eprintln!("{}", err);
+			process::exit(1);
+		}
+
+		return;
+	}
+
+	let grid = process_instructions(&input, 1000, 1000, &OnOffRule).unwrap();
+	println!("Part 1 answer: {}", grid.total(|value| OnOffRule.brightness(value)));
+	if let Some(ref path) = options.image {
+		save_grid_image(&grid, 1000, 1000, path).unwrap();
+	}
+
+	let grid = process_instructions(&input, 1000, 1000, &BrightnessRule).unwrap();
+	println!("Part 2 answer: {}", grid.total(|value| BrightnessRule.brightness(value)));
+	if let Some(ref path) = options.brightness_image {
+		save_brightness_image(&grid, 1000, 1000, path).unwrap();
+	}
 }
@@ -0,0 +1,135 @@
+use std::cmp::max;
+use std::cmp::min;
+use std::ops::Range;
+use Instruction;
+use InstructionType;
+
+/// A grid of lights cut into blocks along the edges of every instruction's
+/// rectangle. All lights in a block always have the same value, so each
+/// instruction only touches the blocks it covers rather than every light.
+pub struct SparseGrid<T> {
+	// the first column of each block, followed by the width of the grid
+	x_edges: Vec<usize>,
+	// the first row of each block, followed by the height of the grid
+	y_edges: Vec<usize>,
+	blocks: Vec<T>,
+}
+
+impl<T: Copy> SparseGrid<T> {
+	pub fn new(width: usize, height: usize, instructions: &[Instruction], initial: T) -> Result<SparseGrid<T>, &'static str> {
+		let mut x_edges = vec![0, width];
+		let mut y_edges = vec![0, height];
+
+		for instruction in instructions {
+			instruction.check_bounds(width, height)?;
+
+			x_edges.push(instruction.upper_left.0 as usize);
+			x_edges.push(instruction.lower_right.0 as usize + 1);
+			y_edges.push(instruction.upper_left.1 as usize);
+			y_edges.push(instruction.lower_right.1 as usize + 1);
+		}
+
+		x_edges.sort();
+		x_edges.dedup();
+		y_edges.sort();
+		y_edges.dedup();
+
+		let block_count = (x_edges.len() - 1) * (y_edges.len() - 1);
+
+		return Ok(SparseGrid {
+			x_edges: x_edges,
+			y_edges: y_edges,
+			blocks: vec![initial; block_count],
+		});
+	}
+
+	pub fn columns(&self) -> usize {
+		self.x_edges.len() - 1
+	}
+
+	/// The blocks, row by row.
+	pub fn blocks(&self) -> &[T] {
+		&self.blocks
+	}
+
+	/// Whether the rectangle is inside the grid.
+	pub fn contains(&self, upper_left: (i32, i32), lower_right: (i32, i32)) -> bool {
+		upper_left.0 >= 0
+			&& upper_left.1 >= 0
+			&& upper_left.0 <= lower_right.0
+			&& upper_left.1 <= lower_right.1
+			&& (lower_right.0 as usize) < *self.x_edges.last().unwrap()
+			&& (lower_right.1 as usize) < *self.y_edges.last().unwrap()
+	}
+
+	/// The columns and rows of the blocks that overlap the rectangle, which
+	/// must be inside the grid.
+	pub fn block_ranges(&self, upper_left: (i32, i32), lower_right: (i32, i32)) -> (Range<usize>, Range<usize>) {
+		let x_range = get_edge_range(&self.x_edges, upper_left.0 as usize, lower_right.0 as usize + 1);
+		let y_range = get_edge_range(&self.y_edges, upper_left.1 as usize, lower_right.1 as usize + 1);
+		return (x_range, y_range);
+	}
+
+	/// The number of lights the block shares with the rectangle.
+	pub fn overlap(&self, x: usize, y: usize, upper_left: (i32, i32), lower_right: (i32, i32)) -> u64 {
+		let width = min(self.x_edges[x + 1], lower_right.0 as usize + 1) - max(self.x_edges[x], upper_left.0 as usize);
+		let height = min(self.y_edges[y + 1], lower_right.1 as usize + 1) - max(self.y_edges[y], upper_left.1 as usize);
+		return width as u64 * height as u64;
+	}
+
+	/// The value of the light at column x and row y, which must be inside the
+	/// grid.
+	pub fn get(&self, x: usize, y: usize) -> T {
+		let column = get_edge_range(&self.x_edges, x, x + 1).start;
+		let row = get_edge_range(&self.y_edges, y, y + 1).start;
+		return self.blocks[row * self.columns() + column];
+	}
+
+	/// Updates every block covered by the instruction. The instruction must be
+	/// one of those the grid was created with.
+	pub fn apply<F>(&mut self, instruction: &Instruction, update: F)
+		where F : Fn(&InstructionType, T) -> T {
+
+		let columns = self.columns();
+		let (x_range, y_range) = self.block_ranges(instruction.upper_left, instruction.lower_right);
+
+		for y in y_range {
+			for x in x_range.clone() {
+				let index = y * columns + x;
+				self.blocks[index] = update(&instruction.instruction_type, self.blocks[index]);
+			}
+		}
+	}
+
+	/// Adds up the value of every light in the grid.
+	pub fn total<F>(&self, value: F) -> u64
+		where F : Fn(T) -> u64 {
+
+		let columns = self.columns();
+		let mut total = 0;
+
+		for (index, block) in self.blocks.iter().enumerate() {
+			let x = index % columns;
+			let y = index / columns;
+			let area = (self.x_edges[x + 1] - self.x_edges[x]) as u64 * (self.y_edges[y + 1] - self.y_edges[y]) as u64;
+			total += value(*block) * area;
+		}
+
+		return total;
+	}
+}
+
+/// The blocks between two edges, where the start edge is inside the grid.
+fn get_edge_range(edges: &[usize], start: usize, end: usize) -> Range<usize> {
+	let first = match edges.binary_search(&start) {
+		Ok(index) => index,
+		Err(index) => index - 1,
+	};
+
+	let last = match edges.binary_search(&end) {
+		Ok(index) => index,
+		Err(index) => index,
+	};
+
+	return first..last;
+}
@@ -0,0 +1,173 @@
+use std::fmt::Debug;
+use Instruction;
+use instructionhistory::InstructionHistory;
+use InstructionType;
+use lightrule::BrightnessRule;
+use lightrule::LightRule;
+use lightrule::OnOffRule;
+use parse_instructions;
+use process_instructions;
+
+/// A small xorshift generator, so that a failing round can be reproduced
+/// from its seed.
+struct Random {
+	state: u64,
+}
+
+impl Random {
+	fn new(seed: u64) -> Random {
+		Random {
+			state: seed | 1,
+		}
+	}
+
+	/// A number from zero up to but not including the bound.
+	fn below(&mut self, bound: usize) -> usize {
+		self.state ^= self.state << 13;
+		self.state ^= self.state >> 7;
+		self.state ^= self.state << 17;
+		return (self.state % bound as u64) as usize;
+	}
+}
+
+/// Runs random instructions on small grids, both on the sparse grid and one
+/// light at a time, and checks that every light ends up the same under both
+/// rules.
+#[test]
+fn sparse_grid_matches_dense_replay() {
+	let mut random = Random::new(1);
+
+	for round in 0..500 {
+		let width = 1 + random.below(40);
+		let height = 1 + random.below(40);
+		let input = random_instructions(&mut random, width, height);
+
+		if let Err(err) = compare(&input, width, height, &OnOffRule) {
+			panic!("round {}, on/off rule: {}\n{}", round, err, input);
+		}
+
+		if let Err(err) = compare(&input, width, height, &BrightnessRule) {
+			panic!("round {}, brightness rule: {}\n{}", round, err, input);
+		}
+	}
+}
+
+/// Asks the instruction history about random regions and steps, and checks
+/// the totals against the lights replayed one at a time.
+#[test]
+fn instruction_history_matches_dense_replay() {
+	let mut random = Random::new(2);
+
+	for round in 0..500 {
+		let width = 1 + random.below(40);
+		let height = 1 + random.below(40);
+		let input = random_instructions(&mut random, width, height);
+
+		if let Err(err) = compare_history(&mut random, &input, width, height, OnOffRule) {
+			panic!("round {}, on/off rule: {}\n{}", round, err, input);
+		}
+
+		if let Err(err) = compare_history(&mut random, &input, width, height, BrightnessRule) {
+			panic!("round {}, brightness rule: {}\n{}", round, err, input);
+		}
+	}
+}
+
+/// Instructions inside the grid, one per line, in the puzzle's format.
+fn random_instructions(random: &mut Random, width: usize, height: usize) -> String {
+	let mut input = String::new();
+
+	for _ in 0..random.below(20) {
+		let instruction_type = match random.below(3) {
+			0 => InstructionType::TurnOn,
+			1 => InstructionType::Toggle,
+			_ => InstructionType::TurnOff,
+		};
+
+		let (x1, x2) = (random.below(width) as i32, random.below(width) as i32);
+		let (y1, y2) = (random.below(height) as i32, random.below(height) as i32);
+
+		let instruction = Instruction {
+			instruction_type: instruction_type,
+			upper_left: (x1.min(x2), y1.min(y2)),
+			lower_right: (x1.max(x2), y1.max(y2)),
+		};
+
+		input.push_str(&format!("{}\n", instruction));
+	}
+
+	return input;
+}
+
+fn compare<R: LightRule>(input: &str, width: usize, height: usize, rule: &R) -> Result<(), String>
+	where R::Value: PartialEq + Debug {
+
+	let expected = replay_dense(&parse_instructions(input), width, height, rule);
+	let grid = process_instructions(input, width, height, rule)?;
+
+	for y in 0..height {
+		for x in 0..width {
+			if grid.get(x, y) != expected[y * width + x] {
+				return Err(format!("light {},{} is {:?} but should be {:?}", x, y, grid.get(x, y), expected[y * width + x]));
+			}
+		}
+	}
+
+	let expected_total: u64 = expected.iter().map(|&value| rule.brightness(value)).sum();
+	let total = grid.total(|value| rule.brightness(value));
+	if total != expected_total {
+		return Err(format!("the total is {} but should be {}", total, expected_total));
+	}
+
+	return Ok(());
+}
+
+/// Asks the instruction history for the total of random regions after a
+/// random number of instructions, with a small snapshot interval so that both
+/// the snapshots and the replay between them are used.
+fn compare_history<R: LightRule + Copy>(random: &mut Random, input: &str, width: usize, height: usize, rule: R) -> Result<(), String> {
+	let instructions = parse_instructions(input);
+	let snapshot_interval = 1 + random.below(4);
+	let history = InstructionHistory::with_snapshot_interval(width, height, parse_instructions(input), rule, snapshot_interval)?;
+
+	for _ in 0..5 {
+		let step = random.below(instructions.len() + 1);
+		let (x1, x2) = (random.below(width), random.below(width));
+		let (y1, y2) = (random.below(height), random.below(height));
+		let upper_left = (x1.min(x2) as i32, y1.min(y2) as i32);
+		let lower_right = (x1.max(x2) as i32, y1.max(y2) as i32);
+
+		let grid = replay_dense(&instructions[..step], width, height, &rule);
+		let mut expected = 0;
+		for y in y1.min(y2)..y1.max(y2) + 1 {
+			for x in x1.min(x2)..x1.max(x2) + 1 {
+				expected += rule.brightness(grid[y * width + x]);
+			}
+		}
+
+		let total = history.total_at(step, upper_left, lower_right)?;
+		if total != expected {
+			return Err(format!(
+				"the total of {},{} through {},{} after {} instructions is {} but should be {}",
+				upper_left.0, upper_left.1, lower_right.0, lower_right.1, step, total, expected));
+		}
+	}
+
+	return Ok(());
+}
+
+/// Runs the instructions on every light, row by row.
+fn replay_dense<R: LightRule>(instructions: &[Instruction], width: usize, height: usize, rule: &R) -> Vec<R::Value> {
+	let mut grid = vec![rule.initial(); width * height];
+
+	for instruction in instructions {
+		for x in instruction.upper_left.0..instruction.lower_right.0 + 1 {
+			for y in instruction.upper_left.1..instruction.lower_right.1 + 1 {
+				let index = ((y as usize) * width) + (x as usize);
+				grid[index] = rule.apply(&instruction.instruction_type, grid[index]);
+			}
+		}
+	}
+
+	return grid;
+}
@@ -1,10 +1,18 @@
+use std::env;
 use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 use std::io;
+use std::process;
 use std::result::Result;
 use std::collections::HashSet;
 
+mod segmentpath;
+#[cfg(test)]
+mod tests;
+
+use segmentpath::SegmentPath;
+
 #[derive(Debug)]
 enum Direction {
     Right,
@@ -17,7 +25,7 @@ struct Step {
     distance: i32,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 struct Location {
     x: i32,
     y: i32,
@@ -137,13 +145,85 @@ fn evaluate_steps(steps: &Vec<Step>, stop_at_revisit: bool) -> i32 {
     x.abs() + y.abs()
 }
 
+/// Finds the first location visited twice without visiting every location,
+/// or the final location if there is none.
+fn evaluate_steps_with_segments(steps: &Vec<Step>) -> i32 {
+    let path = SegmentPath::new(steps);
+    let location = match path.first_crossing() {
+        Some(crossing) => crossing.location,
+        None           => path.end(),
+    };
+
+    location.x.abs() + location.y.abs()
+}
+
+const USAGE: &'static str = "usage: day1 [--crossings] [path]";
+
+struct Options {
+    path: String,
+    crossings: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: "input.txt".to_string(),
+        crossings: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--crossings"              => options.crossings = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => options.path = arg.clone(),
+        };
+    }
+
+    Ok(options)
+}
+
+/// Prints every location that the path walks over again, with the step that
+/// walked over it.
+fn print_crossings(steps: &Vec<Step>) {
+    let path = SegmentPath::new(steps);
+    let segments = path.segments();
+
+    for crossing in path.crossings() {
+        let segment = &segments[crossing.step];
+        println!(
+            "step {} ({}, {},{} to {},{}) crosses {},{}",
+            crossing.step,
+            steps[crossing.step],
+            segment.start.x,
+            segment.start.y,
+            segment.end.x,
+            segment.end.y,
+            crossing.location.x,
+            crossing.location.y);
+    }
+}
+
 fn main() {
-    let input = read_file("input.txt").unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    let input = read_file(&options.path).unwrap();
     let steps = parse_steps(&input).unwrap();
 
+    if options.crossings {
+        print_crossings(&steps);
+        return;
+    }
+
     let part_1_result = evaluate_steps(&steps, false);
     println!("Part 1 result: {}", part_1_result);
     
-    let part_2_result = evaluate_steps(&steps, true);
+    let part_2_result = evaluate_steps_with_segments(&steps);
     println!("Part 2 result: {}", part_2_result);
 }
@@ -0,0 +1,198 @@
+use std::cmp::max;
+use std::cmp::min;
+use Direction;
+use Location;
+use Step;
+
+/// A straight piece of the path, walked from start to end.
+#[derive(Debug)]
+pub struct Segment {
+    pub start: Location,
+    pub end: Location,
+}
+
+/// A location that the path walks over again, found while walking the step
+/// with the given index.
+#[derive(Debug)]
+pub struct Crossing {
+    pub location: Location,
+    pub step: usize,
+}
+
+/// The path made by a list of steps, kept as one segment per step instead of
+/// every location visited, so that long distances cost nothing extra.
+#[derive(Debug)]
+pub struct SegmentPath {
+    segments: Vec<Segment>,
+}
+
+impl Segment {
+    fn min_x(&self) -> i32 { min(self.start.x, self.end.x) }
+    fn max_x(&self) -> i32 { max(self.start.x, self.end.x) }
+    fn min_y(&self) -> i32 { min(self.start.y, self.end.y) }
+    fn max_y(&self) -> i32 { max(self.start.y, self.end.y) }
+
+    /// The piece of this segment that is also on the other one, walked in the
+    /// same direction. Both are axis-aligned, so they meet in a single
+    /// location, along a shared piece of line, or not at all.
+    fn overlap(&self, other: &Segment) -> Option<Segment> {
+        let min_x = max(self.min_x(), other.min_x());
+        let max_x = min(self.max_x(), other.max_x());
+        let min_y = max(self.min_y(), other.min_y());
+        let max_y = min(self.max_y(), other.max_y());
+
+        if min_x > max_x || min_y > max_y {
+            return None;
+        }
+
+        let start = Location {
+            x: max(min_x, min(max_x, self.start.x)),
+            y: max(min_y, min(max_y, self.start.y)),
+        };
+
+        Some(Segment {
+            start: start,
+            end: Location {
+                x: min_x + max_x - start.x,
+                y: min_y + max_y - start.y,
+            },
+        })
+    }
+
+    /// The location after the start, or None if the segment has no length.
+    fn second_location(&self) -> Option<Location> {
+        match self.start == self.end {
+            true  => None,
+            false => Some(Location {
+                x: self.start.x + (self.end.x - self.start.x).signum(),
+                y: self.start.y + (self.end.y - self.start.y).signum(),
+            }),
+        }
+    }
+
+    /// Every location on the segment, from start to end.
+    fn locations(&self) -> Vec<Location> {
+        let length = self.distance_from_start(&self.end);
+        let dx = (self.end.x - self.start.x).signum();
+        let dy = (self.end.y - self.start.y).signum();
+
+        (0..length + 1)
+            .map(|i| Location {
+                x: self.start.x + i * dx,
+                y: self.start.y + i * dy,
+            })
+            .collect()
+    }
+
+    fn distance_from_start(&self, location: &Location) -> i32 {
+        (location.x - self.start.x).abs() + (location.y - self.start.y).abs()
+    }
+}
+
+impl SegmentPath {
+    pub fn new(steps: &Vec<Step>) -> SegmentPath {
+        let mut location = Location {
+            x: 0,
+            y: 0,
+        };
+        let mut direction = 0;
+        let mut segments = Vec::with_capacity(steps.len());
+
+        for step in steps {
+            let direction_delta = match step.direction {
+                Direction::Right => -1,
+                Direction::Left  => 1,
+            };
+
+            direction = (direction + direction_delta + 4) % 4;
+
+            let end = match direction {
+                0 => Location { x: location.x, y: location.y + step.distance },
+                1 => Location { x: location.x + step.distance, y: location.y },
+                2 => Location { x: location.x, y: location.y - step.distance },
+                _ => Location { x: location.x - step.distance, y: location.y },
+            };
+
+            segments.push(Segment {
+                start: location,
+                end: end,
+            });
+
+            location = end;
+        }
+
+        SegmentPath {
+            segments: segments,
+        }
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    pub fn end(&self) -> Location {
+        match self.segments.last() {
+            Some(segment) => segment.end,
+            None          => Location { x: 0, y: 0 },
+        }
+    }
+
+    /// Every location walked over more than once, in the order they are
+    /// reached again. A location reached a third time is reported twice.
+    pub fn crossings(&self) -> Vec<Crossing> {
+        let mut crossings = Vec::new();
+        for index in 0..self.segments.len() {
+            crossings.extend(self.get_crossings_on(index));
+        }
+
+        crossings
+    }
+
+    /// The first location that is visited twice.
+    pub fn first_crossing(&self) -> Option<Crossing> {
+        for (index, segment) in self.segments.iter().enumerate() {
+            let first = self.segments[..index]
+                .iter()
+                .filter_map(|earlier| segment.overlap(earlier))
+                .filter_map(|piece| match piece.start == segment.start {
+                    true  => piece.second_location(),
+                    false => Some(piece.start),
+                })
+                .min_by_key(|location| segment.distance_from_start(location));
+
+            if let Some(location) = first {
+                return Some(Crossing {
+                    location: location,
+                    step: index,
+                });
+            }
+        }
+
+        None
+    }
+
+    /// The locations walked over by a segment that earlier segments already
+    /// visited, in walking order. The start of the segment is where the
+    /// previous one ended, so it does not count.
+    fn get_crossings_on(&self, index: usize) -> Vec<Crossing> {
+        let segment = &self.segments[index];
+
+        let mut locations: Vec<Location> = self.segments[..index]
+            .iter()
+            .filter_map(|earlier| segment.overlap(earlier))
+            .flat_map(|piece| piece.locations())
+            .filter(|location| *location != segment.start)
+            .collect();
+
+        locations.sort_by_key(|location| segment.distance_from_start(location));
+        locations.dedup();
+
+        locations
+            .into_iter()
+            .map(|location| Crossing {
+                location: location,
+                step: index,
+            })
+            .collect()
+    }
+}
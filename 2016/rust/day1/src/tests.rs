@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use Direction;
+use Location;
+use Step;
+use evaluate_steps;
+use evaluate_steps_with_segments;
+use parse_steps;
+use read_file;
+use segmentpath::SegmentPath;
+
+/// A small xorshift generator, so that a failing round can be reproduced
+/// from its seed.
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Random {
+        Random {
+            state: seed | 1,
+        }
+    }
+
+    /// A number from zero up to but not including the bound.
+    fn below(&mut self, bound: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % bound as u64) as usize
+    }
+}
+
+/// Checks the segment path against the walk that visits one location at a
+/// time on the example paths.
+#[test]
+fn segment_path_matches_walk_on_examples() {
+    for path in &["example1.txt", "example2.txt", "example3.txt", "example4.txt"] {
+        let input = read_file(&format!("src/{}", path)).unwrap();
+        let steps = parse_steps(&input).unwrap();
+        if let Err(err) = compare(&steps) {
+            panic!("{}: {}", path, err);
+        }
+    }
+}
+
+/// Checks the segment path against the walk on random short steps, which
+/// cross themselves often.
+#[test]
+fn segment_path_matches_walk_on_random_paths() {
+    let mut random = Random::new(1);
+    for round in 0..1000 {
+        let steps = random_steps(&mut random);
+        if let Err(err) = compare(&steps) {
+            let steps: Vec<String> = steps.iter().map(|step| step.to_string()).collect();
+            panic!("round {}: {}\n{}", round, err, steps.join(", "));
+        }
+    }
+}
+
+fn random_steps(random: &mut Random) -> Vec<Step> {
+    (0..1 + random.below(30))
+        .map(|_| Step {
+            direction: match random.below(2) {
+                0 => Direction::Right,
+                _ => Direction::Left,
+            },
+            distance: random.below(8) as i32,
+        })
+        .collect()
+}
+
+fn compare(steps: &Vec<Step>) -> Result<(), String> {
+    let expected = evaluate_steps(steps, true);
+    let distance = evaluate_steps_with_segments(steps);
+    if distance != expected {
+        return Err(format!("the first location visited twice is {} away but should be {}", distance, expected));
+    }
+
+    let expected = walk_crossings(steps);
+    let crossings: Vec<(usize, Location)> = SegmentPath::new(steps)
+        .crossings()
+        .into_iter()
+        .map(|crossing| (crossing.step, crossing.location))
+        .collect();
+    if crossings != expected {
+        return Err(format!("the crossings are {:?} but should be {:?}", crossings, expected));
+    }
+
+    Ok(())
+}
+
+/// Every location that a step walks over after an earlier step visited it,
+/// found by walking one location at a time.
+fn walk_crossings(steps: &Vec<Step>) -> Vec<(usize, Location)> {
+    let mut location = Location {
+        x: 0,
+        y: 0,
+    };
+    let mut direction = 0;
+    let mut visited: HashSet<Location> = HashSet::new();
+    visited.insert(location);
+
+    let mut crossings = Vec::new();
+    for (index, step) in steps.iter().enumerate() {
+        let direction_delta = match step.direction {
+            Direction::Right => -1,
+            Direction::Left  => 1,
+        };
+
+        direction = (direction + direction_delta + 4) % 4;
+
+        let mut walked = Vec::new();
+        for _ in 0..step.distance {
+            match direction {
+                0 => location.y += 1,
+                1 => location.x += 1,
+                2 => location.y -= 1,
+                _ => location.x -= 1,
+            }
+
+            if visited.contains(&location) {
+                crossings.push((index, location));
+            }
+
+            walked.push(location);
+        }
+
+        visited.extend(walked);
+    }
+
+    crossings
+}
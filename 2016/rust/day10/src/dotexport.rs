@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use factorystate::FactoryState;
+use instruction::Destination;
+use instruction::Instruction;
+use instruction::Microchip;
+
+pub struct DotExporter;
+
+impl DotExporter {
+    /// Writes the bot network as a Graphviz DOT digraph. Bots, outputs and
+    /// input values are nodes and every bot has an edge per rank, labelled
+    /// "low", "high" or "rank N". If the state of a processed run is given,
+    /// each edge is also labelled with the microchips that travelled along it.
+    pub fn export(instructions: &[Instruction], state: Option<&FactoryState>) -> String {
+        let mut bots: BTreeSet<usize> = BTreeSet::new();
+        let mut outputs: BTreeSet<usize> = BTreeSet::new();
+        let mut edges: Vec<(String, String, String)> = Vec::new();
+
+        for instruction in instructions {
+            match *instruction {
+                Instruction::MicrochipGoesTo(microchip, bot)  => {
+                    bots.insert(bot.value());
+                    edges.push((
+                        format!("value_{}", microchip.value()),
+                        format!("bot_{}", bot.value()),
+                        String::new()));
+                },
+                Instruction::BotGives(bot, ref destinations)  => {
+                    bots.insert(bot.value());
+                    for (rank, &destination) in destinations.iter().enumerate() {
+                        match destination {
+                            Destination::Bot(value)    => bots.insert(value),
+                            Destination::Output(value) => outputs.insert(value),
+                        };
+
+                        let label = get_rank_label(rank, destinations.len());
+                        let travelled = match state {
+                            Some(state) => state.get_handed_out_microchips(bot, rank),
+                            None        => Vec::new(),
+                        };
+
+                        let label = if travelled.is_empty() {
+                            label
+                        } else {
+                            format!("{}: {}", label, join(&travelled))
+                        };
+
+                        edges.push((format!("bot_{}", bot.value()), get_node_name(destination), label));
+                    }
+                },
+            };
+        }
+
+        let mut output = String::new();
+        writeln!(output, "digraph factory {{").unwrap();
+
+        for instruction in instructions {
+            if let Instruction::MicrochipGoesTo(microchip, _) = *instruction {
+                writeln!(output, "    value_{} [label=\"value {}\", shape=plaintext];", microchip.value(), microchip.value()).unwrap();
+            }
+        }
+
+        for bot in &bots {
+            writeln!(output, "    bot_{} [label=\"bot {}\", shape=ellipse];", bot, bot).unwrap();
+        }
+
+        for o in &outputs {
+            writeln!(output, "    output_{} [label=\"output {}\", shape=box];", o, o).unwrap();
+        }
+
+        for (from, to, label) in edges {
+            if label.is_empty() {
+                writeln!(output, "    {} -> {};", from, to).unwrap();
+            } else {
+                writeln!(output, "    {} -> {} [label=\"{}\"];", from, to, label).unwrap();
+            }
+        }
+
+        writeln!(output, "}}").unwrap();
+
+        output
+    }
+
+}
+
+fn get_rank_label(rank: usize, count: usize) -> String {
+    match rank {
+        0                     => "low".to_string(),
+        r if r == count - 1   => "high".to_string(),
+        r                     => format!("rank {}", r + 1),
+    }
+}
+
+fn get_node_name(destination: Destination) -> String {
+    match destination {
+        Destination::Bot(value)    => format!("bot_{}", value),
+        Destination::Output(value) => format!("output_{}", value),
+    }
+}
+
+fn join(microchips: &[Microchip]) -> String {
+    microchips
+        .iter()
+        .map(|m| m.value().to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
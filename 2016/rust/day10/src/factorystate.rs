@@ -1,126 +1,172 @@
 use std::collections::HashMap;
+use std::fmt;
 use instruction::Bot;
 use instruction::Destination;
-use instruction::Instruction;
 use instruction::Microchip;
 use instruction::Output;
 
+/// How many microchips a bot holds when there is no rule saying otherwise.
+pub const DEFAULT_BOT_CAPACITY: usize = 2;
+
 #[derive(Debug)]
 pub enum FactoryStateError {
-    BotAlreadyHasTwoMicrochips,
-    BotDoesNotHaveTwoMicrochips,
-    OutputAlreadyHasMicrochip,
+    BotIsFull,
+    BotIsNotFull,
+    OutputIsFull,
+}
+
+impl fmt::Display for FactoryStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FactoryStateError::BotIsFull    => write!(f, "a bot was given a microchip while it was full"),
+            FactoryStateError::BotIsNotFull => write!(f, "a bot gave away its microchips before it was full"),
+            FactoryStateError::OutputIsFull => write!(f, "an output bin was given a microchip while it was full"),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct FactoryState {
     bots: HashMap<Bot, BotState>,
-    output: HashMap<Output, Option<Microchip>>,
+    output: HashMap<Output, Vec<Microchip>>,
+    output_capacity: Option<usize>,
+    /// The microchips each bot gave away, by bot and rank.
+    handed_out: HashMap<(Bot, usize), Vec<Microchip>>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct BotState {
-    pub low: Option<Microchip>,
-    pub high: Option<Microchip>,
+    /// The microchips held by the bot, from lowest to highest.
+    pub microchips: Vec<Microchip>,
+    pub capacity: usize,
+}
+
+impl BotState {
+    fn new() -> BotState {
+        BotState {
+            microchips: Vec::new(),
+            capacity: DEFAULT_BOT_CAPACITY,
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.microchips.len() >= self.capacity
+    }
 }
 
 impl FactoryState {
+    /// A factory where every output bin holds any number of microchips.
     pub fn new() -> FactoryState {
+        FactoryState::with_output_capacity(None)
+    }
+
+    /// A factory where every output bin holds up to the given number of
+    /// microchips, or any number if there is no capacity.
+    pub fn with_output_capacity(output_capacity: Option<usize>) -> FactoryState {
         FactoryState {
             bots: HashMap::new(),
             output: HashMap::new(),
+            output_capacity: output_capacity,
+            handed_out: HashMap::new(),
         }
     }
 
+    pub fn set_bot_capacity(&mut self, bot: Bot, capacity: usize) {
+        self.bots.entry(bot).or_insert_with(BotState::new).capacity = capacity;
+    }
+
     pub fn get_bot_state(&mut self, bot: Bot) -> BotState {
-        *self.bots.entry(bot).or_insert(BotState {
-            low: None,
-            high: None,
-        })
+        self.bots.entry(bot).or_insert_with(BotState::new).clone()
     }
 
+    /// The first microchip that was put in the output.
     pub fn get_output_microchip(&self, output: &Output) -> Option<Microchip> {
         match self.output.get(output) {
-            Some(microchip) => *microchip,
-            None            => None
+            Some(microchips) => microchips.first().cloned(),
+            None             => None
         }
     }
 
-    pub fn execute_instruction(&mut self, instruction: &Instruction) -> Result<(), FactoryStateError> {
-        match instruction {
-            &Instruction::MicrochipGoesTo(microchip, bot) => self.execute_microchip_goes_to_bot(microchip, bot),
-            &Instruction::BotGives(bot, low, high)        => self.execute_bot_gives(bot, low, high),
+    pub fn get_output_microchips(&self, output: &Output) -> Vec<Microchip> {
+        match self.output.get(output) {
+            Some(microchips) => microchips.clone(),
+            None             => Vec::new(),
+        }
+    }
+
+    /// Every bot that is still holding microchips, by number.
+    pub fn get_bots_holding_microchips(&self) -> Vec<Bot> {
+        let mut bots: Vec<Bot> = self.bots
+            .iter()
+            .filter(|&(_, bot_state)| !bot_state.microchips.is_empty())
+            .map(|(&bot, _)| bot)
+            .collect();
+
+        bots.sort_by_key(|b| b.value());
+        bots
+    }
+
+    /// The microchips that the bot gave away from the given rank, where rank 0
+    /// is its lowest microchip, in the order they were given.
+    pub fn get_handed_out_microchips(&self, bot: Bot, rank: usize) -> Vec<Microchip> {
+        match self.handed_out.get(&(bot, rank)) {
+            Some(microchips) => microchips.clone(),
+            None             => Vec::new(),
         }
     }
 
     fn execute_microchip_goes_to_bot(&mut self, microchip: Microchip, bot: Bot) -> Result<(), FactoryStateError> {
-        let bot = self.bots.entry(bot).or_insert(BotState {
-            low: None,
-            high: None,
-        });
-
-        *bot = match *bot {
-            BotState { low: None,      high: None       }                     => BotState { low: Some(microchip), high: None },
-            BotState { low: Some(low), high: None       } if microchip > low  => BotState { low: Some(low), high: Some(microchip) },
-            BotState { low: Some(low), high: None       }                     => BotState { low: Some(microchip), high: Some(low) },
-            BotState { low: None,      high: Some(high) } if microchip < high => BotState { low: Some(microchip), high: Some(high) },
-            BotState { low: None,      high: Some(high) }                     => BotState { low: Some(high), high: Some(microchip) },
-            BotState { low: Some(_),   high: Some(_)    }                     => return Err(FactoryStateError::BotAlreadyHasTwoMicrochips),
-        };
+        let bot = self.bots.entry(bot).or_insert_with(BotState::new);
+
+        if bot.is_full() {
+            return Err(FactoryStateError::BotIsFull);
+        }
+
+        let index = bot.microchips
+            .iter()
+            .position(|m| m.value() > microchip.value())
+            .unwrap_or(bot.microchips.len());
+        bot.microchips.insert(index, microchip);
 
         Ok(())
     }
 
     fn execute_microchip_goes_to_output(&mut self, microchip: Microchip, output: Output) -> Result<(), FactoryStateError> {
-        let output = self.output.entry(output).or_insert(None);
+        let output_capacity = self.output_capacity;
+        let output = self.output.entry(output).or_insert_with(Vec::new);
 
-        *output = match *output {
-            None    => Some(microchip),
-            Some(_) => return Err(FactoryStateError::OutputAlreadyHasMicrochip),
+        match output_capacity {
+            Some(capacity) if output.len() >= capacity => return Err(FactoryStateError::OutputIsFull),
+            _                                          => output.push(microchip),
         };
 
         Ok(())
     }
 
-    fn execute_microchip_goes_to_destination(&mut self, microchip: Microchip, destination: Destination) -> Result<(), FactoryStateError> {
+    pub fn give_microchip(&mut self, microchip: Microchip, destination: Destination) -> Result<(), FactoryStateError> {
         match destination {
             Destination::Bot(value)    => self.execute_microchip_goes_to_bot(microchip, Bot::new(value)),
             Destination::Output(value) => self.execute_microchip_goes_to_output(microchip, Output::new(value)),
         }
     }
 
-    fn execute_bot_gives(&mut self, bot: Bot, low: Destination, high: Destination) -> Result<(), FactoryStateError> {
-        let low_microchip;
-        let high_microchip;
-
-        {
-            let bot_entry = self.bots.entry(bot).or_insert(BotState {
-                low: None,
-                high: None,
-            });
-
-            let (low, high) = match *bot_entry {
-                BotState { low: Some(low), high: Some(high) } => (low, high),
-                _                                             => return Err(FactoryStateError::BotDoesNotHaveTwoMicrochips),
-            };
-
-            low_microchip = low;
-            high_microchip = high;
-        }
+    /// Removes every microchip from a full bot so that they can be given away,
+    /// from lowest to highest.
+    pub fn take_microchips(&mut self, bot: Bot) -> Result<Vec<Microchip>, FactoryStateError> {
+        let microchips: Vec<Microchip> = {
+            let bot_state = self.bots.entry(bot).or_insert_with(BotState::new);
 
-        self.execute_microchip_goes_to_destination(low_microchip, low)?;
-        self.execute_microchip_goes_to_destination(high_microchip, high)?;
+            if !bot_state.is_full() {
+                return Err(FactoryStateError::BotIsNotFull);
+            }
 
-        {
-            let bot_entry = self.bots.entry(bot).or_insert(BotState {
-                low: None,
-                high: None,
-            });
+            bot_state.microchips.drain(..).collect()
+        };
 
-            (*bot_entry).low = None;
-            (*bot_entry).high = None;
+        for (rank, &microchip) in microchips.iter().enumerate() {
+            self.handed_out.entry((bot, rank)).or_insert_with(Vec::new).push(microchip);
         }
 
-        Ok(())
+        Ok(microchips)
     }
-}
\ No newline at end of file
+}
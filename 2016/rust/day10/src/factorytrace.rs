@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::fmt;
+use instruction::Bot;
+use instruction::Destination;
+use instruction::Microchip;
+use instruction::Output;
+
+#[derive(Clone, Debug)]
+pub enum FactoryEvent {
+    ValueAssigned { microchip: Microchip, bot: Bot },
+    /// A full bot compared its microchips, listed from lowest to highest.
+    BotCompared { bot: Bot, microchips: Vec<Microchip> },
+    BotHandedOff { from: Bot, microchip: Microchip, to: Bot },
+    OutputDeposited { from: Bot, microchip: Microchip, output: Output },
+}
+
+impl fmt::Display for FactoryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FactoryEvent::ValueAssigned { microchip, bot }            => {
+                write!(f, "value {} goes to bot {}", microchip.value(), bot.value())
+            },
+            FactoryEvent::BotCompared { bot, ref microchips }         => {
+                let values: Vec<String> = microchips.iter().map(|m| m.value().to_string()).collect();
+                write!(f, "bot {} compares {}", bot.value(), values.join(" with "))
+            },
+            FactoryEvent::BotHandedOff { from, microchip, to }        => {
+                write!(f, "bot {} gives {} to bot {}", from.value(), microchip.value(), to.value())
+            },
+            FactoryEvent::OutputDeposited { from, microchip, output } => {
+                write!(f, "bot {} gives {} to output {}", from.value(), microchip.value(), output.value())
+            },
+        }
+    }
+}
+
+/// Every event that happened while processing instructions, in order. The step
+/// of an event is its index in the log.
+#[derive(Debug)]
+pub struct FactoryTrace {
+    events: Vec<FactoryEvent>,
+    comparisons: HashMap<(usize, usize), Bot>,
+    microchip_steps: HashMap<usize, Vec<usize>>,
+    output_steps: HashMap<usize, Vec<usize>>,
+}
+
+impl FactoryTrace {
+    pub fn new() -> FactoryTrace {
+        FactoryTrace {
+            events: Vec::new(),
+            comparisons: HashMap::new(),
+            microchip_steps: HashMap::new(),
+            output_steps: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, event: FactoryEvent) {
+        let step = self.events.len();
+
+        match event {
+            FactoryEvent::ValueAssigned { microchip, .. } |
+            FactoryEvent::BotHandedOff { microchip, .. }            => {
+                self.microchip_steps.entry(microchip.value()).or_insert_with(Vec::new).push(step);
+            },
+            FactoryEvent::BotCompared { bot, ref microchips }       => {
+                for (i, a) in microchips.iter().enumerate() {
+                    for b in &microchips[i + 1..] {
+                        self.comparisons.insert((a.value(), b.value()), bot);
+                    }
+                }
+            },
+            FactoryEvent::OutputDeposited { microchip, output, .. } => {
+                self.microchip_steps.entry(microchip.value()).or_insert_with(Vec::new).push(step);
+                self.output_steps.entry(output.value()).or_insert_with(Vec::new).push(step);
+            },
+        };
+
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[FactoryEvent] {
+        &self.events
+    }
+
+    /// Finds the bot that compared the two microchips, in either order. A bot
+    /// holding more than two microchips compares each pair of them.
+    pub fn find_comparison(&self, a: Microchip, b: Microchip) -> Option<Bot> {
+        let key = if a.value() < b.value() {
+            (a.value(), b.value())
+        } else {
+            (b.value(), a.value())
+        };
+
+        self.comparisons.get(&key).cloned()
+    }
+
+    /// The bots and the output that a microchip visited, starting with the bot
+    /// it was assigned to.
+    pub fn microchip_path(&self, microchip: Microchip) -> Vec<Destination> {
+        let steps = match self.microchip_steps.get(&microchip.value()) {
+            Some(steps) => steps,
+            None        => return Vec::new(),
+        };
+
+        steps
+            .iter()
+            .map(|&step| match self.events[step] {
+                FactoryEvent::ValueAssigned { bot, .. }      => Destination::Bot(bot.value()),
+                FactoryEvent::BotHandedOff { to, .. }        => Destination::Bot(to.value()),
+                FactoryEvent::OutputDeposited { output, .. } => Destination::Output(output.value()),
+                FactoryEvent::BotCompared { .. }             => unreachable!(),
+            })
+            .collect()
+    }
+
+    /// The microchips in an output before the event at the given step happened.
+    pub fn output_contents_at(&self, output: Output, step: usize) -> Vec<Microchip> {
+        let steps = match self.output_steps.get(&output.value()) {
+            Some(steps) => steps,
+            None        => return Vec::new(),
+        };
+
+        steps
+            .iter()
+            .take_while(|&&s| s < step)
+            .filter_map(|&s| match self.events[s] {
+                FactoryEvent::OutputDeposited { microchip, .. } => Some(microchip),
+                _                                               => None,
+            })
+            .collect()
+    }
+}
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::io;
 use std::fs::File;
 use std::io::BufReader;
@@ -10,9 +11,23 @@ pub enum InstructionError {
     CouldNotReadFile(io::Error),
     CouldNotParseInteger,
     CouldNotParseDestination,
+    CouldNotParseRank,
     CouldNotParseInstruction,
 }
 
+impl fmt::Display for InstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InstructionError::CouldNotOpenFile(ref err) => write!(f, "The instructions could not be opened: {}", err),
+            InstructionError::CouldNotReadFile(ref err) => write!(f, "The instructions could not be read: {}", err),
+            InstructionError::CouldNotParseInteger      => write!(f, "An instruction has a number that could not be parsed."),
+            InstructionError::CouldNotParseDestination  => write!(f, "An instruction gives to something other than a bot or an output."),
+            InstructionError::CouldNotParseRank         => write!(f, "An instruction gives a microchip rank that could not be parsed."),
+            InstructionError::CouldNotParseInstruction  => write!(f, "An instruction could not be parsed."),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Bot(usize);
 
@@ -43,13 +58,19 @@ impl Microchip {
     }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Output(usize);
 
 impl Output {
     pub fn new(value: usize) -> Output {
         Output(value)
     }
+
+    pub fn value(&self) -> usize {
+        match self {
+            &Output(value) => value,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -58,10 +79,13 @@ pub enum Destination {
     Output(usize),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Instruction {
     MicrochipGoesTo(Microchip, Bot),
-    BotGives(Bot, Destination, Destination),
+    /// A bot gives away its microchips once it holds one for each destination.
+    /// The destinations are ordered by the rank of the microchip they receive,
+    /// from lowest to highest.
+    BotGives(Bot, Vec<Destination>),
 }
 
 impl Instruction {
@@ -94,10 +118,11 @@ impl Instruction {
                 goes to \
                 bot (?P<bot>\\d+)").unwrap();
             static ref BOT_GIVES: Regex = Regex::new("^\
-                bot (?P<bot>\\d+) gives low to \
-                (?P<low_type>bot|output) (?P<low_id>\\d+) \
-                and high to \
-                (?P<high_type>bot|output) (?P<high_id>\\d+)$").unwrap();
+                bot (?P<bot>\\d+) gives \
+                (?P<rules>.+)$").unwrap();
+            static ref RANK_GOES_TO: Regex = Regex::new("^\
+                (?P<rank>low|high|rank \\d+) to \
+                (?P<type>bot|output) (?P<id>\\d+)$").unwrap();
         }
 
         match MICROCHIP_GOES_TO.captures(unparsed_instruction) {
@@ -113,12 +138,35 @@ impl Instruction {
         match BOT_GIVES.captures(unparsed_instruction) {
             Some(caps) => {
                 let bot = parse_integer(caps.get(1).unwrap().as_str())?;
-                let low_id = parse_integer(caps.get(3).unwrap().as_str())?;
-                let low = parse_type(caps.get(2).unwrap().as_str(), low_id)?;
-                let high_id = parse_integer(caps.get(5).unwrap().as_str())?;
-                let high = parse_type(caps.get(4).unwrap().as_str(), high_id)?;
 
-                return Ok(Instruction::BotGives(Bot::new(bot), low, high));
+                // The rules look like "low to bot 1 and high to output 2", or
+                // with more ranks, "low to bot 1, rank 2 to bot 3 and high to
+                // output 2".
+                let rules: Vec<&str> = caps.get(2).unwrap().as_str()
+                    .split(" and ")
+                    .flat_map(|r| r.split(", "))
+                    .collect();
+
+                let mut destinations: Vec<Option<Destination>> = vec![None; rules.len()];
+                for rule in rules {
+                    let rule_caps = match RANK_GOES_TO.captures(rule) {
+                        Some(rule_caps) => rule_caps,
+                        None            => return Err(InstructionError::CouldNotParseInstruction),
+                    };
+
+                    let rank = parse_rank(rule_caps.get(1).unwrap().as_str(), destinations.len())?;
+                    let id = parse_integer(rule_caps.get(3).unwrap().as_str())?;
+                    let destination = parse_type(rule_caps.get(2).unwrap().as_str(), id)?;
+
+                    match destinations[rank] {
+                        Some(_) => return Err(InstructionError::CouldNotParseRank),
+                        None    => destinations[rank] = Some(destination),
+                    };
+                }
+
+                let destinations = destinations.into_iter().map(|d| d.unwrap()).collect();
+
+                return Ok(Instruction::BotGives(Bot::new(bot), destinations));
             },
             None       => {}
         };
@@ -134,6 +182,21 @@ fn parse_integer(input: &str) -> Result<usize, InstructionError> {
     }
 }
 
+/// Parses "low", "high" or "rank N" to a zero-based rank, where "rank 1" is
+/// the lowest.
+fn parse_rank(input: &str, count: usize) -> Result<usize, InstructionError> {
+    let rank = match input {
+        "low"  => 1,
+        "high" => count,
+        _      => parse_integer(&input["rank ".len()..])?,
+    };
+
+    match rank {
+        r if r >= 1 && r <= count => Ok(r - 1),
+        _                         => Err(InstructionError::CouldNotParseRank),
+    }
+}
+
 fn parse_type(input: &str, id: usize) -> Result<Destination, InstructionError> {
     match input {
         "bot"    => Ok(Destination::Bot(id)),
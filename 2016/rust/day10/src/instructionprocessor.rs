@@ -1,40 +1,85 @@
-use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
 use instruction::Bot;
+use instruction::Destination;
 use instruction::Instruction;
 use instruction::Microchip;
 use instruction::Output;
-use factorystate::BotState;
 use factorystate::FactoryState;
+use factorystate::FactoryStateError;
+use factorytrace::FactoryEvent;
+use factorytrace::FactoryTrace;
 
 #[derive(Debug)]
 pub enum InstructionProcessorError {
-    CouldNotProcessInstructions,
+    CouldNotExecuteInstruction(FactoryStateError),
+    DuplicateBotRule(Bot),
+    Deadlock(Vec<StuckBot>),
     NoMicrochipInOutput,
 }
 
+impl fmt::Display for InstructionProcessorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InstructionProcessorError::CouldNotExecuteInstruction(ref err) => write!(f, "An instruction could not be executed: {}.", err),
+            InstructionProcessorError::DuplicateBotRule(bot)               => write!(f, "Bot {} has more than one rule.", bot.value()),
+            InstructionProcessorError::Deadlock(ref stuck_bots)            => {
+                write!(f, "The bots stopped with microchips left:")?;
+                for stuck_bot in stuck_bots {
+                    write!(f, "\n  {}", stuck_bot)?;
+                }
+
+                Ok(())
+            },
+            InstructionProcessorError::NoMicrochipInOutput                 => write!(f, "An output bin that was asked about is empty."),
+        }
+    }
+}
+
+/// A bot that ended up holding microchips, either because it never had
+/// enough to be full or because it has no rule to give them away.
 #[derive(Debug)]
-pub struct BotComparison {
-    bot: Bot,
-    low: Microchip,
-    high: Microchip,
+pub struct StuckBot {
+    pub bot: Bot,
+    pub microchips: Vec<Microchip>,
+    pub capacity: usize,
+    pub has_rule: bool,
+    /// The bots that give to this bot but never fired themselves.
+    pub waiting_for: Vec<Bot>,
 }
 
-pub struct InstructionProcessor;
+impl fmt::Display for StuckBot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let microchips: Vec<String> = self.microchips
+            .iter()
+            .map(|m| m.value().to_string())
+            .collect();
+        write!(f, "bot {} holds {} of {} microchips ({})",
+               self.bot.value(), self.microchips.len(), self.capacity, microchips.join(", "))?;
+
+        if !self.has_rule {
+            return write!(f, " but has no rule to give them away");
+        }
 
-impl InstructionProcessor {
-    fn find_comparison(comparisons: &Vec<BotComparison>, low: Microchip, high: Microchip) -> Option<Bot> {
-        for comparison in comparisons {
-            if comparison.low == low && comparison.high == high {
-                return Some(comparison.bot);
-            }
+        if self.waiting_for.len() == 0 {
+            return write!(f, " and no other bot gives to it");
         }
 
-        None
+        let waiting_for: Vec<String> = self.waiting_for
+            .iter()
+            .map(|b| format!("bot {}", b.value()))
+            .collect();
+        write!(f, " and is waiting for {}", waiting_for.join(", "))
     }
+}
+
+pub struct InstructionProcessor;
 
+impl InstructionProcessor {
     pub fn process_and_find_comparison(instructions: Vec<Instruction>, low: Microchip, high: Microchip) -> Result<Option<Bot>, InstructionProcessorError> {
-        let (_, comparisons) = InstructionProcessor::process(instructions)?;
-        let comparison_option = InstructionProcessor::find_comparison(&comparisons, low, high);
+        let (_, trace) = InstructionProcessor::process(instructions)?;
+        let comparison_option = trace.find_comparison(low, high);
 
         Ok(comparison_option)
     }
@@ -55,53 +100,121 @@ impl InstructionProcessor {
         Ok(product)
     }
 
-    pub fn process(instructions: Vec<Instruction>) -> Result<(FactoryState, Vec<BotComparison>), InstructionProcessorError> {
-        let mut state = FactoryState::new();
-        let mut instruction_queue: VecDeque<Instruction> = VecDeque::new();
-        let mut comparisons: Vec<BotComparison> = Vec::new();
-
-        for instruction in instructions {
-            instruction_queue.push_back(instruction);
-        }
-
-        let mut err_count = 0;
+    pub fn process(instructions: Vec<Instruction>) -> Result<(FactoryState, FactoryTrace), InstructionProcessorError> {
+        InstructionProcessor::process_with_state(instructions, FactoryState::new())
+    }
 
-        while instruction_queue.len() > 0 && err_count < instruction_queue.len() {
-            let instruction = instruction_queue.pop_front().unwrap();
+    /// Processes the instructions starting from the given state, which decides
+    /// how many microchips fit in an output bin.
+    pub fn process_with_state(instructions: Vec<Instruction>, mut state: FactoryState) -> Result<(FactoryState, FactoryTrace), InstructionProcessorError> {
+        let mut trace = FactoryTrace::new();
+        let mut rules: HashMap<Bot, Vec<Destination>> = HashMap::new();
+        let mut values: Vec<(Microchip, Bot)> = Vec::new();
 
-            let bot_comparison = match &instruction {
-                &Instruction::BotGives(bot, _, _) => {
-                    match state.get_bot_state(bot) {
-                        BotState { low: Some(low), high: Some(high) } => Some(BotComparison {
-                            bot: bot,
-                            low: low,
-                            high: high,
-                        }),
-                        _                                             => None,
+        for instruction in instructions {
+            match instruction {
+                Instruction::MicrochipGoesTo(microchip, bot) => values.push((microchip, bot)),
+                Instruction::BotGives(bot, destinations)     => {
+                    state.set_bot_capacity(bot, destinations.len());
+                    if rules.insert(bot, destinations).is_some() {
+                        return Err(InstructionProcessorError::DuplicateBotRule(bot));
                     }
                 },
-                _                    => None,
             };
+        }
 
-            match bot_comparison {
-                Some(bot_comparison) => comparisons.push(bot_comparison),
-                None                 => {},
-            };
+        // Deliveries are handled last in, first out so that a bot gives away
+        // its microchips as soon as it becomes full, before any other
+        // microchip moves.
+        let mut fired: HashSet<Bot> = HashSet::new();
+        let mut deliveries: Vec<(Option<Bot>, Microchip, Destination)> = Vec::new();
+
+        for (microchip, bot) in values {
+            deliveries.push((None, microchip, Destination::Bot(bot.value())));
+
+            while let Some((from, microchip, destination)) = deliveries.pop() {
+                if let Err(err) = state.give_microchip(microchip, destination) {
+                    return Err(InstructionProcessorError::CouldNotExecuteInstruction(err));
+                }
+
+                trace.record(match (from, destination) {
+                    (None, Destination::Bot(value))          => FactoryEvent::ValueAssigned { microchip: microchip, bot: Bot::new(value) },
+                    (None, Destination::Output(_))           => unreachable!(),
+                    (Some(from), Destination::Bot(value))    => FactoryEvent::BotHandedOff { from: from, microchip: microchip, to: Bot::new(value) },
+                    (Some(from), Destination::Output(value)) => FactoryEvent::OutputDeposited { from: from, microchip: microchip, output: Output::new(value) },
+                });
+
+                let bot = match destination {
+                    Destination::Bot(value) => Bot::new(value),
+                    Destination::Output(_)  => continue,
+                };
+
+                let destinations = match rules.get(&bot) {
+                    Some(destinations) => destinations,
+                    None               => continue,
+                };
+
+                if !state.get_bot_state(bot).is_full() {
+                    continue;
+                }
+
+                let microchips = match state.take_microchips(bot) {
+                    Ok(microchips) => microchips,
+                    Err(err)       => return Err(InstructionProcessorError::CouldNotExecuteInstruction(err)),
+                };
+
+                fired.insert(bot);
+
+                // Push in reverse so that the lowest microchip is delivered first.
+                for (&microchip, &destination) in microchips.iter().zip(destinations.iter()).rev() {
+                    deliveries.push((Some(bot), microchip, destination));
+                }
+
+                trace.record(FactoryEvent::BotCompared {
+                    bot: bot,
+                    microchips: microchips,
+                });
+            }
+        }
 
-            match state.execute_instruction(&instruction) {
-                Ok(()) => {
-                    err_count = 0;
-                },
-                Err(_) => {
-                    err_count += 1;
-                    instruction_queue.push_back(instruction);
-                },
+        let stuck_bots = InstructionProcessor::find_stuck_bots(&mut state, &rules, &fired);
+        if stuck_bots.len() > 0 {
+            return Err(InstructionProcessorError::Deadlock(stuck_bots));
+        }
+
+        Ok((state, trace))
+    }
+
+    fn find_stuck_bots(state: &mut FactoryState, rules: &HashMap<Bot, Vec<Destination>>, fired: &HashSet<Bot>) -> Vec<StuckBot> {
+        let mut givers: HashMap<usize, Vec<Bot>> = HashMap::new();
+        for (&bot, destinations) in rules {
+            for destination in destinations {
+                if let Destination::Bot(value) = *destination {
+                    givers.entry(value).or_insert_with(Vec::new).push(bot);
+                }
             }
         }
 
-        match err_count {
-            0 => Ok((state, comparisons)),
-            _ => Err(InstructionProcessorError::CouldNotProcessInstructions),
+        let mut stuck_bots: Vec<StuckBot> = Vec::new();
+        for bot in state.get_bots_holding_microchips() {
+            let bot_state = state.get_bot_state(bot);
+
+            let mut waiting_for: Vec<Bot> = match givers.get(&bot.value()) {
+                Some(bots) => bots.iter().filter(|b| !fired.contains(b)).cloned().collect(),
+                None       => Vec::new(),
+            };
+            waiting_for.sort_by_key(|b| b.value());
+            waiting_for.dedup();
+
+            stuck_bots.push(StuckBot {
+                bot: bot,
+                microchips: bot_state.microchips,
+                capacity: bot_state.capacity,
+                has_rule: rules.contains_key(&bot),
+                waiting_for: waiting_for,
+            });
         }
+
+        stuck_bots
     }
 }
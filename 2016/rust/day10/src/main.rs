@@ -1,28 +1,193 @@
 #[macro_use] extern crate lazy_static;
 extern crate regex;
 
+mod dotexport;
 mod factorystate;
+mod factorytrace;
 mod instruction;
 mod instructionprocessor;
 
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::process;
+use dotexport::DotExporter;
+use factorystate::FactoryState;
+use instruction::Destination;
 use instruction::Microchip;
 use instruction::Instruction;
 use instruction::Output;
 use instructionprocessor::InstructionProcessor;
 
+const USAGE: &'static str = "usage: day10 [--trace] [--chip VALUE] [--output VALUE [--step STEP]] [--dot PATH] [--output-capacity N] [path]";
+
+struct Options {
+    path: String,
+    trace: bool,
+    chip: Option<usize>,
+    output: Option<usize>,
+    step: Option<usize>,
+    dot: Option<String>,
+    output_capacity: Option<usize>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: "input.txt".to_string(),
+        trace: false,
+        chip: None,
+        output: None,
+        step: None,
+        dot: None,
+        output_capacity: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--trace"                  => options.trace = true,
+            "--chip"                   => options.chip = Some(parse_number(arg, args.next())?),
+            "--output"                 => options.output = Some(parse_number(arg, args.next())?),
+            "--step"                   => options.step = Some(parse_number(arg, args.next())?),
+            "--output-capacity"        => options.output_capacity = Some(parse_number(arg, args.next())?),
+            "--dot"                    => {
+                options.dot = match args.next() {
+                    Some(path) => Some(path.clone()),
+                    None       => return Err("Expected a path after --dot.".to_string()),
+                };
+            },
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => options.path = arg.clone(),
+        };
+    }
+
+    if options.step.is_some() && options.output.is_none() {
+        return Err("--step needs an --output to look at.".to_string());
+    }
+
+    if options.output_capacity.is_some() && !(options.trace || options.chip.is_some() || options.output.is_some() || options.dot.is_some()) {
+        return Err("--output-capacity needs --trace, --chip, --output or --dot.".to_string());
+    }
+
+    Ok(options)
+}
+
+fn parse_number(option: &str, value: Option<&String>) -> Result<usize, String> {
+    match value.map(|v| v.parse::<usize>()) {
+        Some(Ok(number)) => Ok(number),
+        Some(Err(_))     => Err(format!("Expected a number after {}.", option)),
+        None             => Err(format!("Expected a value after {}.", option)),
+    }
+}
+
+/// Answers the questions about a processed run that were asked on the command
+/// line, instead of printing the puzzle answers.
+fn print_queries(options: &Options) -> Result<(), String> {
+    let instructions = Instruction::parse_file(&options.path).map_err(|err| err.to_string())?;
+    let state = FactoryState::with_output_capacity(options.output_capacity);
+    let (state, trace) = InstructionProcessor::process_with_state(instructions, state).map_err(|err| err.to_string())?;
+
+    if options.trace {
+        for (step, event) in trace.events().iter().enumerate() {
+            println!("{:>5}: {}", step, event);
+        }
+    }
+
+    if let Some(chip) = options.chip {
+        let path: Vec<String> = trace
+            .microchip_path(Microchip::new(chip))
+            .iter()
+            .map(|destination| match *destination {
+                Destination::Bot(value)    => format!("bot {}", value),
+                Destination::Output(value) => format!("output {}", value),
+            })
+            .collect();
+        println!("microchip {}: {}", chip, if path.is_empty() { "never assigned".to_string() } else { path.join(" -> ") });
+    }
+
+    if let Some(output) = options.output {
+        let (microchips, when) = match options.step {
+            Some(step) => (trace.output_contents_at(Output::new(output), step), format!("before step {}", step)),
+            None       => (state.get_output_microchips(&Output::new(output)), "at the end".to_string()),
+        };
+
+        let values: Vec<String> = microchips.iter().map(|m| m.value().to_string()).collect();
+        println!("output {} {}: [{}]", output, when, values.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Writes the bot network as a DOT file. The edges are annotated with the
+/// microchips that travelled them, unless the instructions could not be
+/// processed, in which case the unannotated network is still written so that
+/// the wiring can be inspected.
+fn write_dot(options: &Options, path: &str) -> Result<(), String> {
+    let instructions = Instruction::parse_file(&options.path).map_err(|err| err.to_string())?;
+
+    let state = FactoryState::with_output_capacity(options.output_capacity);
+    let dot = match InstructionProcessor::process_with_state(instructions.clone(), state) {
+        Ok((state, _)) => DotExporter::export(&instructions, Some(&state)),
+        Err(err)       => {
+            eprintln!("{}\nThe edges are not annotated.", err);
+            DotExporter::export(&instructions, None)
+        },
+    };
+
+    let mut file = File::create(path).map_err(|err| err.to_string())?;
+    file.write_all(dot.as_bytes()).map_err(|err| err.to_string())
+}
+
 fn main() {
-    let path = "input.txt";
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    if let Some(ref path) = options.dot {
+        if let Err(err) = write_dot(&options, path) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
 
-    let instructions = Instruction::parse_file(path).unwrap();
+        return;
+    }
+
+    if options.trace || options.chip.is_some() || options.output.is_some() {
+        if let Err(err) = print_queries(&options) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+
+        return;
+    }
+
+    if let Err(err) = print_answers(&options.path) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn print_answers(path: &str) -> Result<(), String> {
+    let instructions = Instruction::parse_file(path).map_err(|err| err.to_string())?;
     let comparison = InstructionProcessor::process_and_find_comparison(
         instructions,
         Microchip::new(17),
-        Microchip::new(61)).unwrap().unwrap().value();
-    println!("Part 1 result: {}", comparison);
+        Microchip::new(61)).map_err(|err| err.to_string())?;
+    match comparison {
+        Some(bot) => println!("Part 1 result: {}", bot.value()),
+        None      => println!("Part 1 result: no bot compared the microchips"),
+    };
 
-    let instructions = Instruction::parse_file(path).unwrap();
+    let instructions = Instruction::parse_file(path).map_err(|err| err.to_string())?;
     let product = InstructionProcessor::process_and_find_output_product(
         instructions,
-        &vec![Output::new(0), Output::new(1), Output::new(2)]).unwrap();
+        &vec![Output::new(0), Output::new(1), Output::new(2)]).map_err(|err| err.to_string())?;
     println!("Part 2 result: {}", product);
-} 
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::Read;
+use std::process;
 use std::result::Result;
 
 struct Keypad {
@@ -7,57 +10,83 @@ struct Keypad {
     initial_position: KeypadPosition,
 }
 
+/// The character that marks a place in a keypad diagram with no key.
 const IGK: char = ' ';
 
+const PART_1_KEYPAD: &'static str = "123\n456\n789";
+
+const PART_2_KEYPAD: &'static str = "  1\n 234\n56789\n ABC\n  D";
+
 impl Keypad {
     fn new_part_1_keypad() -> Keypad {
-        Keypad {
-            keys: vec![
-                vec!['1', '2', '3'],
-                vec!['4', '5', '6'],
-                vec!['7', '8', '9'],
-            ],
-            initial_position: KeypadPosition {
-                row: 1,
-                col: 1,
-            }
-        }
+        Keypad::parse(PART_1_KEYPAD, '5').unwrap()
     }
 
     fn new_part_2_keypad() -> Keypad {
-        Keypad {
-            keys: vec![
-                vec![IGK, IGK, '1', IGK, IGK],
-                vec![IGK, '2', '3', '4', IGK],
-                vec!['5', '6', '7', '8', '9'],
-                vec![IGK, 'A', 'B', 'C', IGK],
-                vec![IGK, IGK, 'D', IGK, IGK],
-            ],
-            initial_position: KeypadPosition {
-                row: 2,
-                col: 0,
+        Keypad::parse(PART_2_KEYPAD, '5').unwrap()
+    }
+
+    /// Reads a keypad from a diagram with one row of keys per line and one key
+    /// per character. Spaces are places without a key and rows can have
+    /// different lengths. The start key must appear exactly once.
+    fn parse(diagram: &str, start_key: char) -> Result<Keypad, String> {
+        if start_key == IGK {
+            return Err("The start key cannot be a space.".to_string());
+        }
+
+        let keys: Vec<Vec<char>> = diagram
+            .lines()
+            .map(|line| line.trim_end().chars().collect())
+            .collect();
+
+        let mut initial_position = None;
+        for (row, row_keys) in keys.iter().enumerate() {
+            for (col, key) in row_keys.iter().enumerate() {
+                if *key != start_key {
+                    continue;
+                }
+
+                if initial_position.is_some() {
+                    return Err(format!("The start key {} appears more than once in the keypad.", start_key));
+                }
+
+                initial_position = Some(KeypadPosition {
+                    row: row,
+                    col: col,
+                });
             }
         }
+
+        match initial_position {
+            Some(initial_position) => Ok(Keypad {
+                keys: keys,
+                initial_position: initial_position,
+            }),
+            None                   => Err(format!("The start key {} is not in the keypad.", start_key)),
+        }
     }
 
-    fn is_not_ignore_key(&self, row: usize, col: usize) -> bool {
-        self.key_at_coords(row, col) != IGK
+    fn is_key(&self, row: usize, col: usize) -> bool {
+        match self.keys.get(row).and_then(|r| r.get(col)) {
+            Some(&key) => key != IGK,
+            None       => false,
+        }
     }
 
     fn can_go_up(&self, row: usize, col: usize) -> bool {
-        row > 0 && self.is_not_ignore_key(row - 1, col)
+        row > 0 && self.is_key(row - 1, col)
     }
 
     fn can_go_down(&self, row: usize, col: usize) -> bool {
-        row < &self.keys.len() - 1 && self.is_not_ignore_key(row + 1, col)
+        self.is_key(row + 1, col)
     }
 
     fn can_go_right(&self, row: usize, col: usize) -> bool {
-        col < &self.keys[col].len() - 1 && self.is_not_ignore_key(row, col + 1)
+        self.is_key(row, col + 1)
     }
 
     fn can_go_left(&self, row: usize, col: usize) -> bool {
-        col > 0 && self.is_not_ignore_key(row, col - 1)
+        col > 0 && self.is_key(row, col - 1)
     }
 
     fn key(&self, position: &KeypadPosition) -> char {
@@ -77,12 +106,28 @@ enum Direction {
     Right,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 struct KeypadPosition {
     row: usize,
     col: usize,
 }
 
+/// A key of the code, with every key the finger was on while finding it,
+/// starting from where the previous key was pressed.
+#[derive(Debug)]
+struct KeyPress {
+    key: char,
+    position: KeypadPosition,
+    path: Vec<char>,
+}
+
+impl fmt::Display for KeyPress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let path: Vec<String> = self.path.iter().map(|k| k.to_string()).collect();
+        write!(f, "{} ({}, {}): {}", self.key, self.position.row, self.position.col, path.join(" -> "))
+    }
+}
+
 
 fn read_file_with_io_error(path: &str) -> std::io::Result<String> {
     let mut file = File::open(path)?;
@@ -131,10 +176,10 @@ fn parse_direction(unparsed_direction: char) -> Result<Direction, String> {
     }
 }
 
-fn evaluate_line(keypad: &Keypad, initial: &KeypadPosition, directions: &Vec<Direction>) -> KeypadPosition {
+fn evaluate_line(keypad: &Keypad, initial: &KeypadPosition, directions: &Vec<Direction>) -> KeyPress {
     let mut row = initial.row;
     let mut col = initial.col;
-    // println!("initial -> {}, {} ({})", row, col, keypad.key_at_coords(row, col));
+    let mut path = vec![keypad.key_at_coords(row, col)];
 
     for direction in directions {
         match *direction {
@@ -145,41 +190,136 @@ fn evaluate_line(keypad: &Keypad, initial: &KeypadPosition, directions: &Vec<Dir
             _                                                 => {},   
         }
 
-        // println!("{:?} -> {}, {} ({})", direction, row, col, keypad.key_at_coords(row, col));
+        path.push(keypad.key_at_coords(row, col));
     }
 
-    KeypadPosition {
+    let position = KeypadPosition {
         row: row,
         col: col
+    };
+
+    KeyPress {
+        key: keypad.key(&position),
+        position: position,
+        path: path,
+    }
+}
+
+fn evaluate_code(keypad: &Keypad, directions: &Vec<Vec<Direction>>) -> Vec<KeyPress> {
+    let mut output = Vec::new();
+    let mut position = keypad.initial_position;
+
+    for line in directions {
+        let key_press = evaluate_line(&keypad, &position, &line);
+        position = key_press.position;
+        output.push(key_press);
     }
+
+    output
 }
 
-fn evaluate_file(keypad: &Keypad, path: &str) -> Result<String, String> {
+fn evaluate_file(keypad: &Keypad, path: &str) -> Result<Vec<KeyPress>, String> {
     let content = read_file(path)?;
     let directions = parse_file(&content)?;
 
-    let mut output = String::new();
-    let mut position = keypad.initial_position;
+    Ok(evaluate_code(keypad, &directions))
+}
 
-    for line in directions {
-        let new_position = evaluate_line(&keypad, &position, &line);
-        let key = keypad.key(&new_position);
-        output.push(key);
+/// Prints the code, and with paths, every key the finger was on for each key
+/// of the code.
+fn print_code(label: &str, keypad: &Keypad, path: &str, paths: bool) -> Result<(), String> {
+    let key_presses = evaluate_file(keypad, path)?;
+    let code: String = key_presses.iter().map(|key_press| key_press.key).collect();
+    println!("{}: {}", label, code);
 
-        position = new_position;
+    if paths {
+        for key_press in &key_presses {
+            println!("  {}", key_press);
+        }
     }
 
-    Ok(output)
+    Ok(())
+}
+
+const USAGE: &'static str = "usage: day2 [--keypad PATH [--start KEY]] [--paths] [path]";
+
+struct Options {
+    path: String,
+    keypad: Option<String>,
+    start_key: Option<char>,
+    paths: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: "input.txt".to_string(),
+        keypad: None,
+        start_key: None,
+        paths: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--keypad"                 => {
+                match args.next() {
+                    Some(keypad) => options.keypad = Some(keypad.clone()),
+                    None         => return Err("Expected a path after --keypad.".to_string()),
+                };
+            },
+            "--start"                  => {
+                let mut chars = args.next().map_or("", |v| &v[..]).chars();
+                options.start_key = match (chars.next(), chars.next()) {
+                    (Some(key), None) => Some(key),
+                    _                 => return Err("Expected a single key after --start.".to_string()),
+                };
+            },
+            "--paths"                  => options.paths = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => options.path = arg.clone(),
+        };
+    }
+
+    if options.start_key.is_some() && options.keypad.is_none() {
+        return Err("--start needs --keypad.".to_string());
+    }
+
+    Ok(options)
 }
 
 fn main() {
-    let path = "input.txt";
-    
-    let part_1_keypad = Keypad::new_part_1_keypad();
-    let part_1_result = evaluate_file(&part_1_keypad, path).unwrap();
-    println!("Part 1 result: {}", part_1_result);
-
-    let part_2_keypad = Keypad::new_part_2_keypad();
-    let part_2_result = evaluate_file(&part_2_keypad, path).unwrap();
-    println!("Part 2 result: {}", part_2_result);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    let keypads = match options.keypad {
+        Some(ref keypad_path) => {
+            let keypad = read_file(keypad_path)
+                .map_err(|err| format!("Could not read {}: {}", keypad_path, err))
+                .and_then(|diagram| Keypad::parse(&diagram, options.start_key.unwrap_or('5')));
+            match keypad {
+                Ok(keypad) => vec![("Result", keypad)],
+                Err(err)   => {
+                    eprintln!("{}", err);
+                    process::exit(1);
+                },
+            }
+        },
+        None                  => vec![
+            ("Part 1 result", Keypad::new_part_1_keypad()),
+            ("Part 2 result", Keypad::new_part_2_keypad()),
+        ],
+    };
+
+    for (label, keypad) in keypads {
+        if let Err(err) = print_code(label, &keypad, &options.path, options.paths) {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
 }
@@ -1,8 +1,17 @@
+use std::env;
+use std::fmt;
 use std::fs::File;
+use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::process;
 use std::str::SplitWhitespace;
 
+mod polygonreader;
+
+use polygonreader::ColumnPolygonReader;
+use polygonreader::LeftoverRows;
+
 #[derive(Debug)]
 struct Triangle {
     side_0: i32,
@@ -35,11 +44,26 @@ impl Triangle {
 }
 
 #[derive(Debug)]
-enum TriangleParseError {
+pub enum TriangleParseError {
     CouldNotOpenFile,
+    ReadError(io::Error),
     NotEnoughSides(i32, i32),
     InvalidSide(i32, i32, String),
-    InvalidNumberOfTriangles,
+    WrongNumberOfColumns(i32, usize, usize),
+}
+
+impl fmt::Display for TriangleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TriangleParseError::CouldNotOpenFile                            => write!(f, "The file could not be opened."),
+            TriangleParseError::ReadError(ref err)                          => write!(f, "The file could not be read: {}", err),
+            TriangleParseError::NotEnoughSides(line_index, side_index)      => write!(f, "Line {} is missing side {}.", line_index + 1, side_index + 1),
+            TriangleParseError::InvalidSide(line_index, side_index, ref s)  => write!(f, "Side {} on line {} is not a number: '{}'.", side_index + 1, line_index + 1, s),
+            TriangleParseError::WrongNumberOfColumns(line_index, expected, actual) => {
+                write!(f, "Line {} has {} columns but the first line has {}.", line_index + 1, actual, expected)
+            },
+        }
+    }
 }
 
 fn parse_triangle_side(line_index: i32, pieces: &mut SplitWhitespace, side_index: i32) -> Result<i32, TriangleParseError> {
@@ -68,85 +92,118 @@ fn parse_triangle_line(line_index: i32, line: &str) -> Result<Triangle, Triangle
     })
 }
 
-fn parse_triangle_file_line_by_line(path: &str) -> Result<Vec<Triangle>, TriangleParseError> {
+/// Counts the valid triangles in the file, one per line, reading a line at a
+/// time.
+fn get_count_of_valid_triangles_by_line(path: &str) -> Result<usize, TriangleParseError> {
     let file = match File::open(path) {
         Err(_)   => return Err(TriangleParseError::CouldNotOpenFile),
         Ok(file) => file,
     };
 
     let file_reader = BufReader::new(file);
-    let mut triangles: Vec<Triangle> = Vec::new();
+    let mut count = 0;
     let mut line_index = 0;
 
     for line_result in file_reader.lines() {
         let line = match line_result {
-            Err(_)   => return Err(TriangleParseError::CouldNotOpenFile),
+            Err(err) => return Err(TriangleParseError::ReadError(err)),
             Ok(line) => line,
         };
 
-        let triangle = parse_triangle_line(line_index, &line)?;
-        triangles.push(triangle);
+        if parse_triangle_line(line_index, &line)?.is_valid() {
+            count += 1;
+        }
 
         line_index += 1;
     }
 
-    Ok(triangles)
+    Ok(count)
 }
 
-fn get_column_triangles_at(index: usize, triangles: &Vec<Triangle>) -> (Triangle, Triangle, Triangle)  {
-    let a = &triangles[index];
-    let b = &triangles[index + 1];
-    let c = &triangles[index + 2];
+/// Counts the valid polygons read down the columns of the file, with each
+/// polygon taking one row per side. Also returns the rows at the end of the
+/// file that were too few to make whole polygons.
+fn get_count_of_valid_polygons_by_column(path: &str, side_count: usize) -> Result<(usize, Option<LeftoverRows>), TriangleParseError> {
+    let file = match File::open(path) {
+        Err(_)   => return Err(TriangleParseError::CouldNotOpenFile),
+        Ok(file) => file,
+    };
 
-    (
-        Triangle {
-            side_0: a.side_0,
-            side_1: b.side_0,
-            side_2: c.side_0,
-        },
-        Triangle {
-            side_0: a.side_1,
-            side_1: b.side_1,
-            side_2: c.side_1,
-        },
-        Triangle {
-            side_0: a.side_2,
-            side_1: b.side_2,
-            side_2: c.side_2,
+    let mut reader = ColumnPolygonReader::new(BufReader::new(file), side_count);
+    let mut count = 0;
+    for polygon in &mut reader {
+        if polygon?.is_valid() {
+            count += 1;
         }
-    )
-}
-
-fn get_triangles_by_column(triangles_by_line: &Vec<Triangle>) -> Result<Vec<Triangle>, TriangleParseError> {
-    if triangles_by_line.len() % 3 != 0 {
-        return Err(TriangleParseError::InvalidNumberOfTriangles);
     }
 
-    let mut output: Vec<Triangle> = Vec::new();
+    Ok((count, reader.leftover_rows()))
+}
 
-    for i in 0..(triangles_by_line.len() / 3) {
-        let (a, b, c) = get_column_triangles_at(3 * i, &triangles_by_line);
-        output.push(a);
-        output.push(b);
-        output.push(c);
-    }
+const USAGE: &'static str = "usage: day3 [--sides N] [path]";
 
-    Ok(output)
+/// The side count only changes part 2, since part 1 reads one triangle per
+/// line.
+struct Options {
+    path: String,
+    side_count: usize,
 }
 
-fn get_count_of_valid_triangles(triangles: &Vec<Triangle>) -> usize {
-    triangles
-        .iter()
-        .filter(|&t| t.is_valid())
-        .count()
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: "input.txt".to_string(),
+        side_count: 3,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--sides"                  => {
+                options.side_count = match args.next().map(|v| v.parse::<usize>()) {
+                    Some(Ok(count)) if count >= 3 => count,
+                    _                             => return Err("Expected a number of sides of at least 3 after --sides.".to_string()),
+                };
+            },
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => options.path = arg.clone(),
+        };
+    }
+
+    Ok(options)
 }
 
 fn main() {
-    let triangles_by_line = parse_triangle_file_line_by_line("input.txt").unwrap();
-    let part_1_result = get_count_of_valid_triangles(&triangles_by_line); 
-    println!("Part 1 result: {}", part_1_result);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    match get_count_of_valid_triangles_by_line(&options.path) {
+        Ok(part_1_result) => println!("Part 1 result: {}", part_1_result),
+        Err(err)          => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
+    };
 
-    let triangles_by_column = get_triangles_by_column(&triangles_by_line).unwrap();
-    let part_2_result = get_count_of_valid_triangles(&triangles_by_column); 
-    println!("Part 2 result: {}", part_2_result);
+    match get_count_of_valid_polygons_by_column(&options.path, options.side_count) {
+        Ok((part_2_result, leftover_rows)) => {
+            println!("Part 2 result: {}", part_2_result);
+            if let Some(leftover_rows) = leftover_rows {
+                eprintln!(
+                    "warning: skipped {} rows from line {}, which are too few for polygons with {} sides",
+                    leftover_rows.count,
+                    leftover_rows.line_index + 1,
+                    options.side_count);
+            }
+        },
+        Err(err)                           => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
+    };
 }
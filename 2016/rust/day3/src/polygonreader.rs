@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::io::Lines;
+use TriangleParseError;
+
+/// A polygon given by the lengths of its sides.
+#[derive(Debug)]
+pub struct Polygon {
+    pub sides: Vec<i32>,
+}
+
+impl Polygon {
+    /// A polygon can be drawn if it has at least three sides and its longest
+    /// side is shorter than all of the other sides put together.
+    pub fn is_valid(&self) -> bool {
+        let longest = match self.sides.iter().max() {
+            Some(&longest) => longest as i64,
+            None           => return false,
+        };
+
+        let total: i64 = self.sides.iter().map(|&s| s as i64).sum();
+
+        self.sides.len() >= 3 && total - longest > longest
+    }
+}
+
+/// Rows at the end of the input that are too few to make a whole group.
+#[derive(Clone, Copy, Debug)]
+pub struct LeftoverRows {
+    pub line_index: i32,
+    pub count: usize,
+}
+
+/// Reads polygons from columns of numbers, a group of rows at a time. Each
+/// group has one row per side, and each column of the group is a polygon, so
+/// only one group is kept in memory no matter how long the input is.
+pub struct ColumnPolygonReader<R: BufRead> {
+    lines: Lines<R>,
+    side_count: usize,
+    column_count: Option<usize>,
+    line_index: i32,
+    pending: VecDeque<Polygon>,
+    leftover_rows: Option<LeftoverRows>,
+    done: bool,
+}
+
+impl<R: BufRead> ColumnPolygonReader<R> {
+    pub fn new(reader: R, side_count: usize) -> ColumnPolygonReader<R> {
+        ColumnPolygonReader {
+            lines: reader.lines(),
+            side_count: side_count,
+            column_count: None,
+            line_index: 0,
+            pending: VecDeque::new(),
+            leftover_rows: None,
+            done: false,
+        }
+    }
+
+    /// The rows that were skipped because the input ended part way through
+    /// a group. Only known once the reader has returned its last polygon.
+    pub fn leftover_rows(&self) -> Option<LeftoverRows> {
+        self.leftover_rows
+    }
+
+    /// Parses a row of sides. Every row must have as many columns as the
+    /// first one.
+    fn parse_row(&mut self, line: &str) -> Result<Vec<i32>, TriangleParseError> {
+        let mut row = Vec::new();
+
+        for (side_index, unparsed_side) in line.split_whitespace().enumerate() {
+            match unparsed_side.parse::<i32>() {
+                Err(_)   => return Err(TriangleParseError::InvalidSide(self.line_index, side_index as i32, unparsed_side.to_string())),
+                Ok(side) => row.push(side),
+            };
+        }
+
+        if row.is_empty() {
+            return Err(TriangleParseError::NotEnoughSides(self.line_index, 0));
+        }
+
+        match self.column_count {
+            Some(count) if count != row.len() => Err(TriangleParseError::WrongNumberOfColumns(self.line_index, count, row.len())),
+            _                                 => {
+                self.column_count = Some(row.len());
+                Ok(row)
+            },
+        }
+    }
+
+    /// Reads the next group of rows into the pending polygons. Returns false
+    /// if the input has ended.
+    fn read_group(&mut self) -> Result<bool, TriangleParseError> {
+        let mut rows: Vec<Vec<i32>> = Vec::with_capacity(self.side_count);
+
+        while rows.len() < self.side_count {
+            let line = match self.lines.next() {
+                None           => break,
+                Some(Err(err)) => return Err(TriangleParseError::ReadError(err)),
+                Some(Ok(line)) => line,
+            };
+
+            let row = self.parse_row(&line)?;
+            rows.push(row);
+            self.line_index += 1;
+        }
+
+        if rows.is_empty() {
+            return Ok(false);
+        }
+
+        // The input ended part way through a group, so the leftover rows
+        // cannot make whole polygons. They are set aside rather than treated
+        // as an error, so that the polygons before them still count.
+        if rows.len() < self.side_count {
+            self.leftover_rows = Some(LeftoverRows {
+                line_index: self.line_index - rows.len() as i32,
+                count: rows.len(),
+            });
+            return Ok(false);
+        }
+
+        for column in 0..rows[0].len() {
+            self.pending.push_back(Polygon {
+                sides: rows.iter().map(|row| row[column]).collect(),
+            });
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Iterator for ColumnPolygonReader<R> {
+    type Item = Result<Polygon, TriangleParseError>;
+
+    fn next(&mut self) -> Option<Result<Polygon, TriangleParseError>> {
+        loop {
+            if let Some(polygon) = self.pending.pop_front() {
+                return Some(Ok(polygon));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            match self.read_group() {
+                Ok(true)  => continue,
+                Ok(false) => {
+                    self.done = true;
+                    return None;
+                },
+                Err(err)  => {
+                    self.done = true;
+                    return Some(Err(err));
+                },
+            };
+        }
+    }
+}
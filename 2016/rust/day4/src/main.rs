@@ -1,9 +1,15 @@
+extern crate regex;
+
 use std::ascii::AsciiExt;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::BufRead;
 use std::io::BufReader;
+use std::process;
+use regex::Regex;
 
 #[derive(Debug)]
 enum RoomParseError {
@@ -13,8 +19,13 @@ enum RoomParseError {
     SectorIdCouldNotBeParsed(String),
     ChecksumMustHaveFiveChars(usize),
     PieceCharsMustBeLowercaseAlphabet(char),
+    NotEnoughDistinctChars(usize),
+    SectorIdMustNotBeNegative(i32),
+    EmptyWord(usize),
 }
 
+const CHECKSUM_LENGTH: usize = 5;
+
 #[derive(Debug)]
 struct Room {
     pieces: Vec<Vec<char>>,
@@ -24,9 +35,15 @@ struct Room {
 
 impl Room {
     fn is_real(&self) -> bool {
+        self.checksum == Room::compute_checksum(&self.pieces)
+    }
+
+    /// The most common letters in the name, ties broken alphabetically. There
+    /// are fewer than five if the name does not have five different letters.
+    fn compute_checksum(pieces: &Vec<Vec<char>>) -> Vec<char> {
         let mut char_counts: HashMap<char, i32> = HashMap::new();
 
-        for piece in &self.pieces {
+        for piece in pieces {
             for c in piece {
                 let entry = char_counts.entry(*c).or_insert(0);
                 *entry += 1;
@@ -44,18 +61,52 @@ impl Room {
             }
         });
 
-        let char_order: Vec<char> = char_counts
+        char_counts
             .iter()
+            .take(CHECKSUM_LENGTH)
             .map(|a| *a.0)
-            .collect();
+            .collect()
+    }
+
+    /// Makes a real room from a plaintext name of lowercase words separated by
+    /// spaces, the inverse of decrypting it. Every word must have at least one
+    /// letter, or the room could not be parsed back.
+    fn encrypt(name: &str, sector_id: i32) -> Result<Room, RoomParseError> {
+        if sector_id < 0 {
+            return Err(RoomParseError::SectorIdMustNotBeNegative(sector_id))
+        }
+
+        let mut pieces: Vec<Vec<char>> = Vec::new();
+
+        for (word_index, word) in name.split(' ').enumerate() {
+            if word.is_empty() {
+                return Err(RoomParseError::EmptyWord(word_index))
+            }
+
+            let mut piece = Vec::new();
 
-        for i in 0..5 {
-            if char_order[i] != self.checksum[i] {
-                return false
+            for c in word.chars() {
+                if !c.is_ascii() || !c.is_alphabetic() || !c.is_lowercase() {
+                    return Err(RoomParseError::PieceCharsMustBeLowercaseAlphabet(c))
+                }
+
+                let index = (((c as i32) - ('a' as i32) + 26 - sector_id % 26) % 26) as u8;
+                piece.push((('a' as u8) + index) as char);
             }
+
+            pieces.push(piece);
         }
 
-        true
+        let checksum = Room::compute_checksum(&pieces);
+        if checksum.len() != CHECKSUM_LENGTH {
+            return Err(RoomParseError::NotEnoughDistinctChars(checksum.len()))
+        }
+
+        Ok(Room {
+            pieces: pieces,
+            sector_id: sector_id,
+            checksum: checksum,
+        })
     }
 
     fn decrypt_room_name(&self) -> String {
@@ -121,7 +172,7 @@ impl Room {
             .chars()
             .collect();
 
-        if checksum.len() != 5 {
+        if checksum.len() != CHECKSUM_LENGTH {
             return Err(RoomParseError::ChecksumMustHaveFiveChars(checksum.len()))
         }
 
@@ -133,6 +184,18 @@ impl Room {
     }
 }
 
+impl fmt::Display for Room {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for piece in &self.pieces {
+            let piece: String = piece.iter().cloned().collect();
+            write!(f, "{}-", piece)?;
+        }
+
+        let checksum: String = self.checksum.iter().cloned().collect();
+        write!(f, "{}[{}]", self.sector_id, checksum)
+    }
+}
+
 fn parse_room_file(path: &str) -> Result<Vec<Room>, RoomParseError> {
     let file = match File::open(path) {
         Err(_)   => return Err(RoomParseError::CouldNotOpenRoomFile),
@@ -166,23 +229,102 @@ fn get_real_room_sector_id_sum(rooms: &Vec<Room>) -> i32 {
     sum
 }
 
-fn get_north_pole_object_storage_sector(rooms: &Vec<Room>) -> Option<i32> {
-    for room in rooms {
-        if room.is_real() && room.decrypt_room_name() == "northpole object storage" {
-            return Some(room.sector_id)
-        }
+/// Finds every real room whose decrypted name matches the pattern.
+fn find_rooms_matching<'a>(rooms: &'a Vec<Room>, pattern: &Regex) -> Vec<&'a Room> {
+    rooms
+        .iter()
+        .filter(|room| room.is_real() && pattern.is_match(&room.decrypt_room_name()))
+        .collect()
+}
+
+/// Finds every real room with the keyword as one of the words of its
+/// decrypted name.
+fn find_rooms_with_keyword<'a>(rooms: &'a Vec<Room>, keyword: &str) -> Vec<&'a Room> {
+    rooms
+        .iter()
+        .filter(|room| room.is_real() && room.decrypt_room_name().split(' ').any(|w| w == keyword))
+        .collect()
+}
+
+const USAGE: &'static str = "usage: day4 [--encrypt NAME SECTOR_ID | --keyword WORD] [path]";
+
+struct Options {
+    path: String,
+    encrypt: Option<(String, i32)>,
+    keyword: Option<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: "input.txt".to_string(),
+        encrypt: None,
+        keyword: None,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--encrypt"                => {
+                options.encrypt = match (args.next(), args.next().map(|v| v.parse::<i32>())) {
+                    (Some(name), Some(Ok(sector_id))) => Some((name.clone(), sector_id)),
+                    _                                 => return Err("Expected a name and a sector ID after --encrypt.".to_string()),
+                };
+            },
+            "--keyword"                => {
+                options.keyword = match args.next() {
+                    Some(keyword) => Some(keyword.clone()),
+                    None          => return Err("Expected a word after --keyword.".to_string()),
+                };
+            },
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => options.path = arg.clone(),
+        };
     }
 
-    None
+    Ok(options)
 }
 
 fn main() {
-    let rooms = parse_room_file("input.txt").unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    // Print the room for a plaintext name instead of solving the puzzle.
+    if let Some((ref name, sector_id)) = options.encrypt {
+        match Room::encrypt(name, sector_id) {
+            Ok(room) => println!("{}", room),
+            Err(err) => {
+                eprintln!("{:?}", err);
+                process::exit(1);
+            },
+        };
+
+        return;
+    }
+
+    let rooms = parse_room_file(&options.path).unwrap();
+
+    // List the real rooms with a word in their name instead of solving the
+    // puzzle.
+    if let Some(ref keyword) = options.keyword {
+        for room in find_rooms_with_keyword(&rooms, keyword) {
+            println!("{}: {}", room, room.decrypt_room_name());
+        }
+
+        return;
+    }
 
     let part_1_result = get_real_room_sector_id_sum(&rooms);
     println!("Part 1 result: {}", part_1_result);
 
-    let part_2_result = get_north_pole_object_storage_sector(&rooms).unwrap();
-    println!("Part 2 result: {}", part_2_result);
+    let north_pole_object_storage = Regex::new("^northpole object storage$").unwrap();
+    match find_rooms_matching(&rooms, &north_pole_object_storage).first() {
+        Some(room) => println!("Part 2 result: {}", room.sector_id),
+        None       => println!("Part 2 result: not found"),
+    };
 }
-    
\ No newline at end of file
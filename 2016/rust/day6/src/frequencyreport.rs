@@ -0,0 +1,224 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use CharSelection;
+
+/// Weights are added up in millionths of a vote, so that weights which sum to
+/// the same amount compare as equal.
+const UNITS_PER_VOTE: f64 = 1_000_000.0;
+
+/// A letter and the total weight of the votes for it in a column.
+#[derive(Debug, Clone, Copy)]
+pub struct Candidate {
+    pub letter: char,
+    pub weight: f64,
+    units: i64,
+}
+
+/// The weights of a column add up to more votes than can be counted.
+#[derive(Debug)]
+pub struct WeightOverflow {
+    pub column: usize,
+}
+
+impl fmt::Display for WeightOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The weights in column {} add up to more votes than can be counted.", self.column)
+    }
+}
+
+#[derive(Debug)]
+pub struct ColumnReport {
+    /// Every letter seen in the column, from the best to the worst candidate.
+    pub candidates: Vec<Candidate>,
+    pub total_weight: f64,
+}
+
+/// How each letter of a message was chosen from the columns of many noisy
+/// copies of it.
+#[derive(Debug)]
+pub struct FrequencyReport {
+    pub columns: Vec<ColumnReport>,
+}
+
+impl ColumnReport {
+    /// Returns None if the weights add up to more units than fit in an i64.
+    fn new(column: &Vec<char>, weights: &[f64], selection: CharSelection) -> Option<ColumnReport> {
+        let mut letter_units: HashMap<char, i64> = HashMap::new();
+        let mut total_units: i64 = 0;
+
+        for (row, c) in column.iter().enumerate() {
+            let weight = weights.get(row).cloned().unwrap_or(1.0);
+            let scaled = (weight * UNITS_PER_VOTE).round();
+            if scaled.is_nan() || scaled.abs() >= i64::MAX as f64 {
+                return None;
+            }
+
+            let units = scaled as i64;
+            let letter_units = letter_units.entry(*c).or_insert(0);
+            *letter_units = letter_units.checked_add(units)?;
+            total_units = total_units.checked_add(units)?;
+        }
+
+        let mut candidates: Vec<Candidate> = letter_units
+            .into_iter()
+            .map(|(letter, units)| Candidate {
+                letter: letter,
+                weight: units as f64 / UNITS_PER_VOTE,
+                units: units,
+            })
+            .collect();
+
+        // Between equal weights, the later letter is the most common and the
+        // earlier letter is the least common.
+        candidates.sort_by(|a, b| {
+            let order = match a.units.cmp(&b.units) {
+                Ordering::Equal => a.letter.cmp(&b.letter),
+                other           => other,
+            };
+
+            match selection {
+                CharSelection::LeastCommon => order,
+                CharSelection::MostCommon  => order.reverse(),
+            }
+        });
+
+        Some(ColumnReport {
+            candidates: candidates,
+            total_weight: total_units as f64 / UNITS_PER_VOTE,
+        })
+    }
+
+    pub fn winner(&self) -> Option<Candidate> {
+        self.candidates.first().cloned()
+    }
+
+    pub fn runner_up(&self) -> Option<Candidate> {
+        self.candidates.get(1).cloned()
+    }
+
+    /// The gap between the winner and the runner-up as a share of all votes,
+    /// from 0 for a tie to 1 when every vote agrees.
+    pub fn margin(&self) -> f64 {
+        let winner = match self.winner() {
+            Some(winner) => winner.weight,
+            None         => return 0.0,
+        };
+
+        let runner_up = match self.runner_up() {
+            Some(runner_up) => runner_up.weight,
+            None            => 0.0,
+        };
+
+        if self.total_weight == 0.0 {
+            return 0.0;
+        }
+
+        (winner - runner_up).abs() / self.total_weight
+    }
+
+    /// The letters that share the winner's weight, if there is more than one.
+    pub fn tied_letters(&self) -> Vec<char> {
+        let winner = match self.winner() {
+            Some(winner) => winner,
+            None         => return Vec::new(),
+        };
+
+        let tied: Vec<char> = self.candidates
+            .iter()
+            .filter(|c| c.units == winner.units)
+            .map(|c| c.letter)
+            .collect();
+
+        match tied.len() {
+            1 => Vec::new(),
+            _ => tied,
+        }
+    }
+
+    pub fn is_tie(&self) -> bool {
+        !self.tied_letters().is_empty()
+    }
+}
+
+impl FrequencyReport {
+    /// Every row gets one vote.
+    pub fn new(columns: &Vec<Vec<char>>, selection: CharSelection) -> FrequencyReport {
+        // One vote per row cannot overflow.
+        FrequencyReport::with_weights(columns, &[], selection).unwrap()
+    }
+
+    /// Each row's vote counts as much as its weight. Rows without a weight
+    /// count once.
+    pub fn with_weights(columns: &Vec<Vec<char>>, weights: &[f64], selection: CharSelection) -> Result<FrequencyReport, WeightOverflow> {
+        let mut column_reports = Vec::with_capacity(columns.len());
+        for (index, column) in columns.iter().enumerate() {
+            match ColumnReport::new(column, weights, selection) {
+                Some(column_report) => column_reports.push(column_report),
+                None                => return Err(WeightOverflow { column: index }),
+            };
+        }
+
+        Ok(FrequencyReport {
+            columns: column_reports,
+        })
+    }
+
+    pub fn message(&self) -> String {
+        self.columns
+            .iter()
+            .filter_map(|c| c.winner())
+            .map(|c| c.letter)
+            .collect()
+    }
+
+    /// The message made of each column's runner-up, or a '?' where a column
+    /// only has one letter.
+    pub fn second_best_message(&self) -> String {
+        self.columns
+            .iter()
+            .filter(|c| !c.candidates.is_empty())
+            .map(|c| match c.runner_up() {
+                Some(runner_up) => runner_up.letter,
+                None            => '?',
+            })
+            .collect()
+    }
+
+    /// The columns where the winner is tied with another letter.
+    pub fn ties(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|&(_, c)| c.is_tie())
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+impl fmt::Display for FrequencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, column) in self.columns.iter().enumerate() {
+            let winner = match column.winner() {
+                Some(winner) => winner,
+                None         => continue,
+            };
+
+            write!(f, "{:>3}: {} ({:.1} of {:.1}, margin {:.1}%)", index, winner.letter, winner.weight, column.total_weight, column.margin() * 100.0)?;
+
+            if let Some(runner_up) = column.runner_up() {
+                write!(f, ", runner-up {} ({:.1})", runner_up.letter, runner_up.weight)?;
+            }
+
+            let tied = column.tied_letters();
+            if !tied.is_empty() {
+                let tied: String = tied.into_iter().collect();
+                write!(f, ", tie between {}", tied)?;
+            }
+
+            writeln!(f, "")?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,12 +1,28 @@
+use std::env;
+use std::fmt;
 use std::fs::File;
-use std::cmp::Ordering;
-use std::collections::HashMap;
 use std::io::BufReader;
 use std::io::BufRead;
+use std::process;
+
+mod frequencyreport;
+
+use frequencyreport::FrequencyReport;
+use frequencyreport::WeightOverflow;
 
 #[derive(Debug)]
 enum ColumnFileError {
     CouldNotReadFile,
+    InvalidWeight(usize, String),
+}
+
+impl fmt::Display for ColumnFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColumnFileError::CouldNotReadFile                    => write!(f, "The file could not be read."),
+            ColumnFileError::InvalidWeight(line_index, ref line) => write!(f, "The weight on line {} is not a finite number of at least 0: '{}'.", line_index + 1, line),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -15,37 +31,6 @@ enum CharSelection {
     MostCommon,
 }
 
-fn get_most_frequent_letter(chars: &Vec<char>, selection: CharSelection) -> Option<char> {
-    let mut char_counts: HashMap<char, i32> = HashMap::new();
-
-    for c in chars {
-        let entry = char_counts.entry(*c).or_insert(0);
-        *entry += 1;
-    }
-
-    if char_counts.len() == 0 {
-        return None;
-    }
-
-    let mut char_counts: Vec<(&char, &i32)> = char_counts
-        .iter()
-        .collect();
-
-    char_counts.sort_by(|a, b| {
-        match a.1.cmp(b.1) { 
-            Ordering::Equal => a.0.cmp(b.0),
-            other           => other,
-        }
-    });
-
-    let entry = match selection {
-        CharSelection::LeastCommon => char_counts.first(),
-        CharSelection::MostCommon  => char_counts.last(),
-    };
-
-    Some(*entry.unwrap().0)
-}
-
 fn get_char_columns(path: &str) -> Result<Vec<Vec<char>>, ColumnFileError> {
     let file = match File::open(path) {
         Ok(file) => file,
@@ -76,25 +61,127 @@ fn get_char_columns(path: &str) -> Result<Vec<Vec<char>>, ColumnFileError> {
     Ok(columns)
 }
 
-fn select_column_letters(columns: &Vec<Vec<char>>, selection: CharSelection) -> String {
-    let mut output = String::new();
+/// Reads one weight per line, for the row of the same index. Weights must be
+/// finite and at least 0.
+fn get_weights(path: &str) -> Result<Vec<f64>, ColumnFileError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_)   => return Err(ColumnFileError::CouldNotReadFile),
+    };
+
+    let file_reader = BufReader::new(file);
+    let mut weights: Vec<f64> = Vec::new();
+
+    for (line_index, line_result) in file_reader.lines().enumerate() {
+        let line = match line_result {
+            Ok(line) => line,
+            Err(_)   => return Err(ColumnFileError::CouldNotReadFile),
+        };
+
+        match line.trim().parse::<f64>() {
+            Ok(weight) if weight.is_finite() && weight >= 0.0 => weights.push(weight),
+            _                                                 => return Err(ColumnFileError::InvalidWeight(line_index, line)),
+        };
+    }
+
+    Ok(weights)
+}
+
+fn get_report(columns: &Vec<Vec<char>>, weights: &Option<Vec<f64>>, selection: CharSelection) -> Result<FrequencyReport, WeightOverflow> {
+    match *weights {
+        Some(ref weights) => FrequencyReport::with_weights(columns, weights, selection),
+        None              => Ok(FrequencyReport::new(columns, selection)),
+    }
+}
+
+const USAGE: &'static str = "usage: day6 [--weights PATH] [--report] [path]";
 
-    for column in columns {
-        match get_most_frequent_letter(&column, selection) {
-            Some(c) => output.push(c),
-            None    => {},
+struct Options {
+    path: String,
+    weights: Option<String>,
+    report: bool,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: "input.txt".to_string(),
+        weights: None,
+        report: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--weights"                => {
+                options.weights = match args.next() {
+                    Some(path) => Some(path.clone()),
+                    None       => return Err("Expected a path after --weights.".to_string()),
+                };
+            },
+            "--report"                 => options.report = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => options.path = arg.clone(),
         };
     }
 
-    output
+    Ok(options)
+}
+
+/// Prints how each letter was chosen, the message made of the runners-up and
+/// the columns where the winner is tied.
+fn print_report(report: &FrequencyReport) {
+    print!("{}", report);
+    println!("Second best: {}", report.second_best_message());
+
+    let ties: Vec<String> = report.ties().iter().map(|column| column.to_string()).collect();
+    match ties.len() {
+        0 => println!("No ties"),
+        _ => println!("Ties in columns {}", ties.join(", ")),
+    };
 }
 
 fn main() {
-    let columns = get_char_columns("input.txt").unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
 
-    let part_1_result = select_column_letters(&columns, CharSelection::MostCommon);
-    println!("Part 1 result: {}", part_1_result);
+    let columns = match get_char_columns(&options.path) {
+        Ok(columns) => columns,
+        Err(err)    => {
+            eprintln!("{}", err);
+            process::exit(1);
+        },
+    };
 
-    let part_2_result = select_column_letters(&columns, CharSelection::LeastCommon);
-    println!("Part 2 result: {}", part_2_result);
+    // A bad weights file is reported like a bad option.
+    let weights = match options.weights {
+        Some(ref path) => match get_weights(path) {
+            Ok(weights) => Some(weights),
+            Err(err)    => {
+                eprintln!("{}\n{}", err, USAGE);
+                process::exit(2);
+            },
+        },
+        None           => None,
+    };
+
+    for &(label, selection) in &[("Part 1 result", CharSelection::MostCommon), ("Part 2 result", CharSelection::LeastCommon)] {
+        let report = match get_report(&columns, &weights, selection) {
+            Ok(report) => report,
+            Err(err)   => {
+                eprintln!("{}", err);
+                process::exit(1);
+            },
+        };
+
+        println!("{}: {}", label, report.message());
+        if options.report {
+            print_report(&report);
+        }
+    }
 }
@@ -1,11 +1,24 @@
+use std::env;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufRead;
-use std::collections::HashSet;
+use std::process;
+
+mod pattern;
+
+use pattern::PairedPatternRule;
+use pattern::Pattern;
+use pattern::PatternRule;
+use pattern::Requirement;
 
 #[derive(Debug)]
 enum IpAddressParseError {
     CouldNotReadFile,
+    /// A bracket was opened at the second position before the one opened at
+    /// the first position was closed.
+    NestedBracket(usize, usize),
+    UnexpectedClosingBracket(usize),
+    UnclosedBracket(usize),
 }
 
 #[derive(Debug)]
@@ -16,144 +29,82 @@ struct IpAddress {
 #[derive(Debug)]
 struct IpAddressSequence {
     is_hypernet: bool,
+    start: usize,
     chars: Vec<char>,
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-struct Aba {
-    value: Vec<char>
-}
-
-impl Aba {
-    fn get_bab(&self) -> Aba {
-        let mut bab: Vec<char> = Vec::new();
-
-        bab.push(self.value[1]);
-        bab.push(self.value[0]);
-        bab.push(self.value[1]);
-
-        Aba {
-            value: bab
-        }
-    }
-}
-
 impl IpAddressSequence {
-    fn maybe_push_new(is_hypernet: bool, chars: Vec<char>, sequences: &mut Vec<IpAddressSequence>) {
+    fn maybe_push_new(is_hypernet: bool, start: usize, chars: Vec<char>, sequences: &mut Vec<IpAddressSequence>) {
         if chars.len() > 0 {
             sequences.push(IpAddressSequence {
                 is_hypernet: is_hypernet,
+                start: start,
                 chars: chars,
             });
         }
     }
-
-    fn has_abba(&self) -> bool {
-        if self.chars.len() < 4 {
-            return false;
-        }
-
-        for i in 3..self.chars.len() {
-            if self.chars[i - 3] == self.chars[i] &&
-               self.chars[i - 2] == self.chars[i - 1] &&
-               self.chars[i - 3] != self.chars[i - 2]
-            {
-                return true;
-            }
-        }
-
-        false
-    }
-
-    fn get_abas(&self) -> HashSet<Aba> {
-        let mut abas: HashSet<Aba> = HashSet::new();
-
-        if self.chars.len() < 3 {
-            return abas;
-        }
-
-        for i in 2..self.chars.len() {
-            if self.chars[i - 2] == self.chars[i] &&
-               self.chars[i - 1] != self.chars[i]
-            {
-                let aba = &self.chars[(i - 2)..(i + 1)];
-                let aba = aba.to_vec();
-
-                abas.insert(Aba {
-                    value: aba
-                });
-            }
-        }
-
-        abas
-    }
 }
 
 
 impl IpAddress {
-    fn parse(unparsed: &str) -> IpAddress {
+    /// Splits the address into sequences outside and inside of brackets.
+    /// Brackets cannot be nested and every bracket must be closed.
+    fn parse(unparsed: &str) -> Result<IpAddress, IpAddressParseError> {
         let mut sequences: Vec<IpAddressSequence> = Vec::new();
         let mut chars: Vec<char> = Vec::new();
-        let mut is_hypernet = false;
-
-        for c in unparsed.chars() {
-            let is_sequence_complete = match c {
-                '[' => true,
-                ']' => { is_hypernet = true; true },
-                _   => false,
+        let mut start = 0;
+        let mut open_bracket: Option<usize> = None;
+
+        for (position, c) in unparsed.chars().enumerate() {
+            match (c, open_bracket) {
+                ('[', Some(open)) => return Err(IpAddressParseError::NestedBracket(open, position)),
+                (']', None)       => return Err(IpAddressParseError::UnexpectedClosingBracket(position)),
+                ('[', None)       => {
+                    IpAddressSequence::maybe_push_new(false, start, chars, &mut sequences);
+                    open_bracket = Some(position);
+                },
+                (']', Some(_))    => {
+                    IpAddressSequence::maybe_push_new(true, start, chars, &mut sequences);
+                    open_bracket = None;
+                },
+                _                 => {
+                    chars.push(c);
+                    continue;
+                },
             };
 
-            if is_sequence_complete {
-                IpAddressSequence::maybe_push_new(is_hypernet, chars, &mut sequences);
-                chars = Vec::new();
-                is_hypernet = false;
-            } else {
-                chars.push(c);
-            }
+            chars = Vec::new();
+            start = position + 1;
         }
-        
-        IpAddressSequence::maybe_push_new(is_hypernet, chars, &mut sequences);
 
-        IpAddress {
-            sequences: sequences
+        if let Some(open) = open_bracket {
+            return Err(IpAddressParseError::UnclosedBracket(open));
         }
-    }
 
-    fn supports_tls(&self) -> bool {
-        let mut has_abba = false;
+        IpAddressSequence::maybe_push_new(false, start, chars, &mut sequences);
 
-        for sequence in &self.sequences {
-            if sequence.has_abba() {
-                has_abba = true;
+        Ok(IpAddress {
+            sequences: sequences
+        })
+    }
 
-                if sequence.is_hypernet {
-                    return false;
-                }                
-            } 
-        }
+    fn supports_tls(&self) -> bool {
+        let rule = PatternRule {
+            pattern: Pattern::palindrome(4),
+            outside: Requirement::Required,
+            inside: Requirement::Forbidden,
+        };
 
-        has_abba
+        rule.matches(self)
     }
 
     fn supports_ssl(&self) -> bool {
-        let mut supernet_abas: HashSet<Aba> = HashSet::new();
-        let mut hypernet_abas: HashSet<Aba> = HashSet::new();
-
-        for s in &self.sequences {
-            let abas = s.get_abas();
-            for aba in abas {
-                if s.is_hypernet {
-                    hypernet_abas.insert(aba.get_bab());
-                } else {
-                    supernet_abas.insert(aba);
-                }
-            }
-        }
+        let rule = PairedPatternRule {
+            outside: Pattern::new("aba"),
+            inside: Pattern::new("bab"),
+        };
 
-        supernet_abas
-            .intersection(&hypernet_abas)
-            .next()
-            .is_some()
+        rule.matches(self)
     }
 }
 
@@ -172,7 +123,7 @@ fn parse_ip_address_file(path: &str) -> Result<Vec<IpAddress>, IpAddressParseErr
             Err(_)   => return Err(IpAddressParseError::CouldNotReadFile),
         };
 
-        ip_addresses.push(IpAddress::parse(&line));
+        ip_addresses.push(IpAddress::parse(&line)?);
     }
 
     Ok(ip_addresses)
@@ -192,8 +143,130 @@ fn count_supporting_ssl(ip_addresses: &Vec<IpAddress>) -> usize {
         .count()
 }
 
+const USAGE: &'static str = "usage: day7 [--pattern TEMPLATE [--outside REQ] [--inside REQ] | --paired OUTSIDE INSIDE] [path], where REQ is required, forbidden or ignored";
+
+enum Query {
+    Rule(PatternRule),
+    Paired(PairedPatternRule),
+}
+
+struct Options {
+    path: String,
+    query: Option<Query>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut path = "input.txt".to_string();
+    let mut template: Option<String> = None;
+    let mut outside = Requirement::Required;
+    let mut inside = Requirement::Ignored;
+    let mut paired: Option<(String, String)> = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--pattern"                => {
+                template = match args.next() {
+                    Some(template) => Some(template.clone()),
+                    None           => return Err("Expected a template after --pattern.".to_string()),
+                };
+            },
+            "--outside"                => outside = parse_requirement(arg, args.next())?,
+            "--inside"                 => inside = parse_requirement(arg, args.next())?,
+            "--paired"                 => {
+                paired = match (args.next(), args.next()) {
+                    (Some(outside), Some(inside)) => Some((outside.clone(), inside.clone())),
+                    _                             => return Err("Expected two templates after --paired.".to_string()),
+                };
+            },
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => path = arg.clone(),
+        };
+    }
+
+    let query = match (template, paired) {
+        (Some(_), Some(_))                  => return Err("Use either --pattern or --paired, not both.".to_string()),
+        (Some(template), None)              => Some(Query::Rule(PatternRule {
+            pattern: Pattern::new(&template),
+            outside: outside,
+            inside: inside,
+        })),
+        (None, Some((outside, inside)))     => Some(Query::Paired(PairedPatternRule {
+            outside: Pattern::new(&outside),
+            inside: Pattern::new(&inside),
+        })),
+        (None, None)                        => None,
+    };
+
+    Ok(Options {
+        path: path,
+        query: query,
+    })
+}
+
+fn parse_requirement(option: &str, value: Option<&String>) -> Result<Requirement, String> {
+    match value.map(|v| &v[..]) {
+        Some("required")  => Ok(Requirement::Required),
+        Some("forbidden") => Ok(Requirement::Forbidden),
+        Some("ignored")   => Ok(Requirement::Ignored),
+        Some(other)       => Err(format!("Unknown requirement '{}'.", other)),
+        None              => Err(format!("Expected a requirement after {}.", option)),
+    }
+}
+
+/// Prints the addresses that match the rule, with the character position of
+/// every match, followed by how many matched.
+fn print_query(ip_addresses: &Vec<IpAddress>, query: &Query) {
+    let mut count = 0;
+
+    for (line_index, ip_address) in ip_addresses.iter().enumerate() {
+        let positions: Vec<String> = match *query {
+            Query::Rule(ref rule)     => {
+                if !rule.matches(ip_address) {
+                    continue;
+                }
+
+                rule.find(ip_address)
+                    .iter()
+                    .map(|&(is_hypernet, ref m)| format!("{} at {}", if is_hypernet { "inside" } else { "outside" }, m.position))
+                    .collect()
+            },
+            Query::Paired(ref rule)   => {
+                if !rule.matches(ip_address) {
+                    continue;
+                }
+
+                rule.find(ip_address)
+                    .iter()
+                    .map(|&(ref outside, ref inside)| format!("outside at {} with inside at {}", outside.position, inside.position))
+                    .collect()
+            },
+        };
+
+        println!("line {}: {}", line_index + 1, positions.join(", "));
+        count += 1;
+    }
+
+    println!("{} of {} addresses match", count, ip_addresses.len());
+}
+
 fn main() {
-    let ip_addresses = parse_ip_address_file("input.txt").unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    let ip_addresses = parse_ip_address_file(&options.path).unwrap();
+
+    // Look for a pattern of our own instead of solving the puzzle.
+    if let Some(ref query) = options.query {
+        print_query(&ip_addresses, query);
+        return;
+    }
 
     let part_1_result = count_supporting_tls(&ip_addresses);
     println!("Part 1 result: {}", part_1_result);
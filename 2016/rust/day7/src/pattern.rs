@@ -0,0 +1,182 @@
+use std::char;
+use IpAddress;
+
+/// A run of characters described by a template, where each letter of the
+/// template stands for one character. The same letter must be the same
+/// character and different letters must be different characters, so "abba"
+/// matches "xyyx" but not "xxxx".
+#[derive(Debug)]
+pub struct Pattern {
+    template: Vec<char>,
+}
+
+/// Where a pattern was found, as a character position in the whole address,
+/// and which character each letter of the template stood for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMatch {
+    pub position: usize,
+    pub bindings: Vec<(char, char)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Requirement {
+    /// At least one sequence of this kind must contain the pattern.
+    Required,
+    /// No sequence of this kind may contain the pattern.
+    Forbidden,
+    Ignored,
+}
+
+/// A pattern with a requirement for the sequences outside brackets
+/// (supernet) and one for those inside brackets (hypernet).
+#[derive(Debug)]
+pub struct PatternRule {
+    pub pattern: Pattern,
+    pub outside: Requirement,
+    pub inside: Requirement,
+}
+
+/// Two patterns sharing template letters. An address matches if the first
+/// pattern appears outside brackets and the second appears inside brackets
+/// with every shared letter standing for the same character.
+#[derive(Debug)]
+pub struct PairedPatternRule {
+    pub outside: Pattern,
+    pub inside: Pattern,
+}
+
+impl Pattern {
+    pub fn new(template: &str) -> Pattern {
+        Pattern {
+            template: template.chars().collect(),
+        }
+    }
+
+    /// A palindrome of the given length where every character of its first
+    /// half is different, such as "abba" for 4 or "aba" for 3.
+    pub fn palindrome(length: usize) -> Pattern {
+        Pattern {
+            template: (0..length)
+                .map(|i| char::from_u32('a' as u32 + i.min(length - 1 - i) as u32).unwrap())
+                .collect(),
+        }
+    }
+
+    /// Finds every match in a run of characters that starts at the given
+    /// position of the address.
+    pub fn find_in(&self, chars: &[char], start: usize) -> Vec<PatternMatch> {
+        let mut matches = Vec::new();
+
+        if self.template.is_empty() || chars.len() < self.template.len() {
+            return matches;
+        }
+
+        for offset in 0..(chars.len() - self.template.len() + 1) {
+            let window = &chars[offset..(offset + self.template.len())];
+            if let Some(bindings) = self.bind(window) {
+                matches.push(PatternMatch {
+                    position: start + offset,
+                    bindings: bindings,
+                });
+            }
+        }
+
+        matches
+    }
+
+    /// The character for each template letter, sorted by letter, if the
+    /// window matches.
+    fn bind(&self, window: &[char]) -> Option<Vec<(char, char)>> {
+        let mut bindings: Vec<(char, char)> = Vec::new();
+
+        for (&letter, &c) in self.template.iter().zip(window) {
+            match bindings.iter().find(|&&(l, _)| l == letter) {
+                Some(&(_, bound)) if bound != c => return None,
+                Some(_)                         => continue,
+                None                            => {},
+            };
+
+            if bindings.iter().any(|&(_, bound)| bound == c) {
+                return None;
+            }
+
+            bindings.push((letter, c));
+        }
+
+        bindings.sort();
+        Some(bindings)
+    }
+}
+
+impl PatternMatch {
+    /// Whether both matches agree on every template letter they share.
+    fn agrees_with(&self, other: &PatternMatch) -> bool {
+        self.bindings.iter().all(|&(letter, c)| {
+            other.bindings
+                .iter()
+                .all(|&(other_letter, other_c)| other_letter != letter || other_c == c)
+        })
+    }
+}
+
+impl PatternRule {
+    /// Every match in the address, with whether it is inside brackets.
+    pub fn find(&self, address: &IpAddress) -> Vec<(bool, PatternMatch)> {
+        let mut matches = Vec::new();
+
+        for sequence in &address.sequences {
+            for m in self.pattern.find_in(&sequence.chars, sequence.start) {
+                matches.push((sequence.is_hypernet, m));
+            }
+        }
+
+        matches
+    }
+
+    pub fn matches(&self, address: &IpAddress) -> bool {
+        let matches = self.find(address);
+        let found_outside = matches.iter().any(|&(is_hypernet, _)| !is_hypernet);
+        let found_inside = matches.iter().any(|&(is_hypernet, _)| is_hypernet);
+
+        is_met(self.outside, found_outside) && is_met(self.inside, found_inside)
+    }
+}
+
+impl PairedPatternRule {
+    /// Every pair of an outside match and an inside match that agree.
+    pub fn find(&self, address: &IpAddress) -> Vec<(PatternMatch, PatternMatch)> {
+        let mut outside_matches = Vec::new();
+        let mut inside_matches = Vec::new();
+
+        for sequence in &address.sequences {
+            if sequence.is_hypernet {
+                inside_matches.extend(self.inside.find_in(&sequence.chars, sequence.start));
+            } else {
+                outside_matches.extend(self.outside.find_in(&sequence.chars, sequence.start));
+            }
+        }
+
+        let mut pairs = Vec::new();
+        for outside_match in &outside_matches {
+            for inside_match in &inside_matches {
+                if outside_match.agrees_with(inside_match) {
+                    pairs.push((outside_match.clone(), inside_match.clone()));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    pub fn matches(&self, address: &IpAddress) -> bool {
+        !self.find(address).is_empty()
+    }
+}
+
+fn is_met(requirement: Requirement, found: bool) -> bool {
+    match requirement {
+        Requirement::Required  => found,
+        Requirement::Forbidden => !found,
+        Requirement::Ignored   => true,
+    }
+}
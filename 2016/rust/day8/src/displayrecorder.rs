@@ -0,0 +1,148 @@
+use CommandType;
+use Display;
+
+/// Undoes one command. A rotation is undone by the opposite rotation, but a
+/// rectangle overwrites pixels, so the pixels it covered are kept instead.
+#[derive(Debug)]
+enum Undo {
+    Command(CommandType),
+    Restore(Vec<Vec<bool>>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PixelChange {
+    pub row: usize,
+    pub column: usize,
+    pub on: bool,
+}
+
+/// Plays a list of commands on a display one step at a time, in either
+/// direction. Step k is the display after the first k commands.
+#[derive(Debug)]
+pub struct DisplayRecorder {
+    display: Display,
+    commands: Vec<CommandType>,
+    undo: Vec<Undo>,
+}
+
+impl DisplayRecorder {
+    pub fn new(display: Display, commands: Vec<CommandType>) -> DisplayRecorder {
+        DisplayRecorder {
+            display: display,
+            commands: commands,
+            undo: Vec::new(),
+        }
+    }
+
+    pub fn step(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// The command that takes the display from step `index` to the next.
+    pub fn command(&self, index: usize) -> CommandType {
+        self.commands[index]
+    }
+
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    /// Executes the next command. Returns false if every command has run.
+    pub fn step_forward(&mut self) -> bool {
+        let command_type = match self.commands.get(self.step()) {
+            Some(&command_type) => command_type,
+            None                => return false,
+        };
+
+        let undo = match command_type {
+            CommandType::Rect(width, height)         => {
+                Undo::Restore(self.display.pixels[..height]
+                    .iter()
+                    .map(|row| row[..width].to_vec())
+                    .collect())
+            },
+            CommandType::RotateColumn(column, count) => {
+                let height = self.display.pixels.len();
+                Undo::Command(CommandType::RotateColumn(column, height - count % height))
+            },
+            CommandType::RotateRow(row, count)       => {
+                let width = self.display.pixels[row].len();
+                Undo::Command(CommandType::RotateRow(row, width - count % width))
+            },
+        };
+
+        self.display.execute_command(&command_type);
+        self.undo.push(undo);
+
+        true
+    }
+
+    /// Reverts the previous command. Returns false if no command has run.
+    pub fn step_backward(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(Undo::Command(command_type)) => self.display.execute_command(&command_type),
+            Some(Undo::Restore(pixels))       => {
+                for (row, previous_row) in pixels.into_iter().enumerate() {
+                    for (column, pixel) in previous_row.into_iter().enumerate() {
+                        self.display.pixels[row][column] = pixel;
+                    }
+                }
+            },
+            None                              => return false,
+        };
+
+        true
+    }
+
+    pub fn seek(&mut self, step: usize) {
+        while self.step() < step && self.step_forward() {}
+        while self.step() > step && self.step_backward() {}
+    }
+
+    pub fn render_at(&mut self, step: usize) -> String {
+        self.seek(step);
+        self.display.pretty_print()
+    }
+
+    /// The pixels that differ between two steps, with their value at the
+    /// second step.
+    pub fn diff(&mut self, from: usize, to: usize) -> Vec<PixelChange> {
+        self.seek(from);
+        let before = self.display.pixels.clone();
+        self.seek(to);
+
+        let mut changes = Vec::new();
+        for (row, pixels) in self.display.pixels.iter().enumerate() {
+            for (column, &pixel) in pixels.iter().enumerate() {
+                if pixel != before[row][column] {
+                    changes.push(PixelChange {
+                        row: row,
+                        column: column,
+                        on: pixel,
+                    });
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// Finds the last command, by index, that changed a pixel on the way to
+    /// the given step.
+    pub fn find_last_change(&mut self, row: usize, column: usize, step: usize) -> Option<usize> {
+        self.seek(step);
+        let pixel = self.display.pixels[row][column];
+
+        while self.step_backward() {
+            if self.display.pixels[row][column] != pixel {
+                return Some(self.step());
+            }
+        }
+
+        None
+    }
+}
@@ -1,6 +1,27 @@
+use std::env;
+use std::fmt;
 use std::fs::File;
 use std::io::BufReader;
 use std::io::BufRead;
+use std::io;
+use std::process;
+
+#[cfg(feature = "png")]
+extern crate png;
+
+// The image module is shared with other crates, and this one only saves
+// bitmaps.
+#[allow(dead_code)]
+mod image;
+use image::BitmapImage;
+
+mod displayrecorder;
+mod ocr;
+
+use displayrecorder::DisplayRecorder;
+use ocr::OcrError;
+
+const USAGE: &'static str = "usage: day8 [--step K] [--diff FROM TO] [--blame ROW COLUMN] [--image PATH] [path]";
 
 #[derive(Debug)]
 enum CommandTypeParseError {
@@ -10,7 +31,7 @@ enum CommandTypeParseError {
     UnknownCommand,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 enum CommandType {
     Rect(usize, usize),
     RotateColumn(usize, usize),
@@ -46,6 +67,19 @@ impl Display {
         output
     }
 
+    fn to_image(&self) -> BitmapImage {
+        let width = self.pixels.first().map(|r| r.len()).unwrap_or(0);
+        BitmapImage::from_fn(width, self.pixels.len(), |x, y| self.pixels[y][x])
+    }
+
+    fn save_image(&self, path: &str) -> io::Result<()> {
+        self.to_image().save(path)
+    }
+
+    fn read_letters(&self) -> Result<String, OcrError> {
+        ocr::read_letters(&self.pixels)
+    }
+
     fn execute_command(&mut self, command_type: &CommandType) {
         match command_type {
             &CommandType::Rect(width, height)         => self.execute_rect(width, height),
@@ -111,6 +145,16 @@ impl Display {
     }
 }
 
+impl fmt::Display for CommandType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CommandType::Rect(width, height)         => write!(f, "rect {}x{}", width, height),
+            CommandType::RotateColumn(column, count) => write!(f, "rotate column x={} by {}", column, count),
+            CommandType::RotateRow(row, count)       => write!(f, "rotate row y={} by {}", row, count),
+        }
+    }
+}
+
 impl CommandType {
     fn parse(unparsed: &str) -> Result<CommandType, CommandTypeParseError> {
         let pieces: Vec<&str> = unparsed.split(|c| c == ' ' || c == '=').collect();
@@ -197,14 +241,135 @@ fn parse_command_types(path: &str) -> Result<Vec<CommandType>, CommandTypeParseE
     Ok(command_types)
 }
 
+/// A question about the screen program, answered by playing it back.
+enum Query {
+    Step(usize),
+    Diff(usize, usize),
+    Blame(usize, usize),
+    /// Saves the display after the last command as an image.
+    Image(String),
+}
+
+fn parse_options(args: &[String]) -> Result<(String, Vec<Query>), String> {
+    let mut path = "input.txt".to_string();
+    let mut queries = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--step"                   => queries.push(Query::Step(parse_number(arg, args.next())?)),
+            "--diff"                   => queries.push(Query::Diff(parse_number(arg, args.next())?, parse_number(arg, args.next())?)),
+            "--blame"                  => queries.push(Query::Blame(parse_number(arg, args.next())?, parse_number(arg, args.next())?)),
+            "--image"                  => {
+                match args.next() {
+                    Some(image_path) => queries.push(Query::Image(image_path.clone())),
+                    None             => return Err("Expected a path after --image.".to_string()),
+                };
+            },
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => path = arg.clone(),
+        };
+    }
+
+    Ok((path, queries))
+}
+
+fn parse_number(option: &str, value: Option<&String>) -> Result<usize, String> {
+    match value.map(|v| v.parse::<usize>()) {
+        Some(Ok(number)) => Ok(number),
+        Some(Err(_))     => Err(format!("Expected a number after {}.", option)),
+        None             => Err(format!("Expected a value after {}.", option)),
+    }
+}
+
+fn print_query(recorder: &mut DisplayRecorder, query: &Query) -> Result<(), String> {
+    let len = recorder.len();
+
+    let last_step = match *query {
+        Query::Step(step)     => step,
+        Query::Diff(from, to) => from.max(to),
+        Query::Blame(_, _)    => len,
+        Query::Image(_)       => len,
+    };
+
+    if last_step > len {
+        return Err(format!("There are only {} steps.", len));
+    }
+
+    if let Query::Blame(row, column) = *query {
+        if recorder.display().pixels.get(row).map_or(true, |r| column >= r.len()) {
+            return Err(format!("There is no pixel at row {} and column {}.", row, column));
+        }
+    }
+
+    match *query {
+        Query::Step(step)         => {
+            let frame = recorder.render_at(step);
+            match step {
+                0 => println!("step 0 of {}, before any command:", len),
+                _ => println!("step {} of {}, after {}:", step, len, recorder.command(step - 1)),
+            };
+            print!("{}", frame);
+            println!("{} pixels on", recorder.display().count_on_pixels());
+        },
+        Query::Diff(from, to)     => {
+            let changes = recorder.diff(from, to);
+            println!("{} pixels changed from step {} to step {}", changes.len(), from, to);
+            for change in changes {
+                println!("  row {} column {} turned {}", change.row, change.column, if change.on { "on" } else { "off" });
+            }
+        },
+        Query::Blame(row, column) => {
+            match recorder.find_last_change(row, column, len) {
+                Some(index) => println!("row {} column {} was last changed by step {}: {}", row, column, index + 1, recorder.command(index)),
+                None        => println!("row {} column {} was never changed", row, column),
+            };
+        },
+        Query::Image(ref path)    => {
+            recorder.seek(len);
+            recorder.display().save_image(path).map_err(|err| format!("Could not save {}: {}", path, err))?;
+            println!("saved the display to {}", path);
+        },
+    };
+
+    Ok(())
+}
+
 fn main() {
-    let command_types = parse_command_types("input.txt").unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (path, queries) = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    let command_types = parse_command_types(&path).unwrap();
+
+    if !queries.is_empty() {
+        let mut recorder = DisplayRecorder::new(Display::new(50, 6), command_types);
+        for query in &queries {
+            if let Err(err) = print_query(&mut recorder, query) {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        }
+
+        return;
+    }
+
     let mut display = Display::new(50, 6);
     display.execute_commands(&command_types);
 
     let part_1_result = display.count_on_pixels();
     println!("Part 1 result: {}", part_1_result);
 
-    println!("Part 2 result:");
-    println!("{}", display.pretty_print());
+    match display.read_letters() {
+        Ok(letters) => println!("Part 2 result: {}", letters),
+        Err(err)    => {
+            println!("Part 2 result: {}", err);
+            println!("{}", display.pretty_print());
+        },
+    };
 }
@@ -0,0 +1,112 @@
+use std::fmt;
+
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 6;
+
+/// The letters drawn by Advent of Code puzzles. Each letter is four pixels wide
+/// followed by a blank column, except for Y, which uses all five.
+const FONT: [(char, [&'static str; GLYPH_HEIGHT]); 18] = [
+    ('A', [".##..", "#..#.", "#..#.", "####.", "#..#.", "#..#."]),
+    ('B', ["###..", "#..#.", "###..", "#..#.", "#..#.", "###.."]),
+    ('C', [".##..", "#..#.", "#....", "#....", "#..#.", ".##.."]),
+    ('E', ["####.", "#....", "###..", "#....", "#....", "####."]),
+    ('F', ["####.", "#....", "###..", "#....", "#....", "#...."]),
+    ('G', [".##..", "#..#.", "#....", "#.##.", "#..#.", ".###."]),
+    ('H', ["#..#.", "#..#.", "####.", "#..#.", "#..#.", "#..#."]),
+    ('I', [".###.", "..#..", "..#..", "..#..", "..#..", ".###."]),
+    ('J', ["..##.", "...#.", "...#.", "...#.", "#..#.", ".##.."]),
+    ('K', ["#..#.", "#.#..", "##...", "#.#..", "#.#..", "#..#."]),
+    ('L', ["#....", "#....", "#....", "#....", "#....", "####."]),
+    ('O', [".##..", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('P', ["###..", "#..#.", "#..#.", "###..", "#....", "#...."]),
+    ('R', ["###..", "#..#.", "#..#.", "###..", "#.#..", "#..#."]),
+    ('S', [".###.", "#....", "#....", ".##..", "...#.", "###.."]),
+    ('U', ["#..#.", "#..#.", "#..#.", "#..#.", "#..#.", ".##.."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####.", "...#.", "..#..", ".#...", "#....", "####."]),
+];
+
+#[derive(Debug)]
+pub enum OcrError {
+    WrongHeight(usize),
+    UnrecognizedGlyphs(Vec<UnrecognizedGlyph>),
+}
+
+/// A glyph that is not in the font, with its position in the text and its
+/// pixels drawn with '#' and '.'.
+#[derive(Debug)]
+pub struct UnrecognizedGlyph {
+    pub index: usize,
+    pub rows: Vec<String>,
+}
+
+impl fmt::Display for OcrError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OcrError::WrongHeight(height)             => {
+                write!(f, "expected {} rows of pixels but found {}", GLYPH_HEIGHT, height)
+            },
+            OcrError::UnrecognizedGlyphs(ref glyphs) => {
+                write!(f, "could not recognize {} glyph(s)", glyphs.len())?;
+                for glyph in glyphs {
+                    write!(f, "\nglyph {}:", glyph.index)?;
+                    for row in &glyph.rows {
+                        write!(f, "\n{}", row)?;
+                    }
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Splits the pixels into glyphs five columns wide and reads each of them as a
+/// letter. A glyph with no pixels on is read as a space.
+pub fn read_letters(pixels: &Vec<Vec<bool>>) -> Result<String, OcrError> {
+    if pixels.len() != GLYPH_HEIGHT {
+        return Err(OcrError::WrongHeight(pixels.len()));
+    }
+
+    let width = pixels.iter().map(|r| r.len()).max().unwrap_or(0);
+    let glyph_count = (width + GLYPH_WIDTH - 1) / GLYPH_WIDTH;
+
+    let mut output = String::new();
+    let mut unrecognized = Vec::new();
+
+    for index in 0..glyph_count {
+        let rows: Vec<String> = pixels
+            .iter()
+            .map(|row| (0..GLYPH_WIDTH)
+                .map(|i| match row.get(index * GLYPH_WIDTH + i) {
+                    Some(&true) => '#',
+                    _           => '.',
+                })
+                .collect())
+            .collect();
+
+        match read_glyph(&rows) {
+            Some(letter) => output.push(letter),
+            None         => unrecognized.push(UnrecognizedGlyph {
+                index: index,
+                rows: rows,
+            }),
+        };
+    }
+
+    match unrecognized.len() {
+        0 => Ok(output),
+        _ => Err(OcrError::UnrecognizedGlyphs(unrecognized)),
+    }
+}
+
+fn read_glyph(rows: &[String]) -> Option<char> {
+    if rows.iter().all(|r| !r.contains('#')) {
+        return Some(' ');
+    }
+
+    FONT
+        .iter()
+        .find(|&&(_, ref glyph)| glyph.iter().zip(rows).all(|(a, b)| a == b))
+        .map(|&(letter, _)| letter)
+}
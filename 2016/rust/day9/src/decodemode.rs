@@ -0,0 +1,13 @@
+/// How the raw bytes of the input file are turned into the characters that the
+/// tokenizer and the decompressors operate on. Marker lengths and output
+/// lengths are counted in these characters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecodeMode {
+    /// Only 7-bit ASCII is accepted. This is the puzzle format.
+    Ascii,
+    /// Every byte is one character, so arbitrary binary data can be wrapped.
+    /// Bytes are mapped to the code points U+0000 through U+00FF.
+    Bytes,
+    /// The input is decoded as UTF-8 and every code point is one character.
+    Utf8,
+}
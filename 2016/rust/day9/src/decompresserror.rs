@@ -1,7 +1,10 @@
-#[derive(Clone, Copy, Debug)]
-pub enum DecompressError {
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecompressErrorKind {
     CouldNotReadFile,
     NonAsciiCharEncountered,
+    InvalidUtf8Sequence,
     ExpectedInteger,
     ExpectedX,
     ExpectedCloseParenthesis,
@@ -9,3 +12,60 @@ pub enum DecompressError {
     InvalidRepeatDirective,
     ExpectedMoreCharacters,
 }
+
+#[derive(Clone, Debug)]
+pub struct DecompressError {
+    pub kind: DecompressErrorKind,
+    /// The byte offset in the input where the problem was found.
+    pub offset: usize,
+    /// The text of the repeat directive being read when the problem was found,
+    /// e.g. "(10x". This is empty outside of a directive.
+    pub marker: String,
+    /// A description of the token that was expected at the offset, if any.
+    pub expected: Option<&'static str>,
+}
+
+impl DecompressError {
+    pub fn new(kind: DecompressErrorKind, offset: usize) -> DecompressError {
+        let expected = match kind {
+            DecompressErrorKind::ExpectedInteger          => Some("integer"),
+            DecompressErrorKind::ExpectedX                => Some("'x'"),
+            DecompressErrorKind::ExpectedCloseParenthesis => Some("')'"),
+            DecompressErrorKind::ExpectedMoreCharacters   => Some("character"),
+            _                                             => None,
+        };
+
+        DecompressError {
+            kind: kind,
+            offset: offset,
+            marker: String::new(),
+            expected: expected,
+        }
+    }
+
+    pub fn in_marker(mut self, marker: &str) -> DecompressError {
+        self.marker = marker.to_string();
+        self
+    }
+
+    pub fn expecting(mut self, expected: &'static str) -> DecompressError {
+        self.expected = Some(expected);
+        self
+    }
+}
+
+impl fmt::Display for DecompressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} at byte {}", self.kind, self.offset)?;
+
+        if !self.marker.is_empty() {
+            write!(f, " in marker \"{}\"", self.marker)?;
+        }
+
+        if let Some(expected) = self.expected {
+            write!(f, " (expected {})", expected)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,13 +1,14 @@
 use std::io;
 use std::collections::VecDeque;
-use std::iter::Peekable;
+use decodemode::DecodeMode;
 use decompresserror::DecompressError;
 use decompresstokens::DecompressTokens;
 use decompresstokens::DecompressToken;
 use decompresstokens::DecompressTokenType;
 
 pub struct Decompressor {
-    tokens: Peekable<DecompressTokens>,
+    tokens: DecompressTokens,
+    mode: DecodeMode,
     state: State,
     text: VecDeque<char>,
     repeat_sequence: Vec<char>,
@@ -24,14 +25,15 @@ enum State {
 }
 
 impl Decompressor  {
-    pub fn open(path: &str) -> Result<Decompressor, io::Error> {
-        let tokens = match DecompressTokens::open(path) {
+    pub fn open(path: &str, mode: DecodeMode) -> Result<Decompressor, io::Error> {
+        let tokens = match DecompressTokens::open(path, mode) {
             Ok(tokens) => tokens,
             Err(err)   => return Err(err),
         };
 
         Ok(Decompressor {
-            tokens: tokens.peekable(),
+            tokens: tokens,
+            mode: mode,
             state: State::Initial,
             text: VecDeque::new(),
             repeat_sequence: Vec::new(),
@@ -53,7 +55,6 @@ impl Decompressor  {
         Ok(output)
     }
 
-    #[allow(dead_code)]
     pub fn read_to_end(&mut self) -> Result<String, DecompressError> {
         let mut output = String::new();
         
@@ -67,8 +68,27 @@ impl Decompressor  {
         Ok(output)
     }
 
-    fn start_repeat_directive(&mut self) -> Option<Result<char, DecompressError>> {
-        match self.consume_repeat_directive() {
+    /// Decompresses the rest of the input into raw bytes. In the byte mode every
+    /// character maps back to the byte it was read from. Otherwise, the output
+    /// is encoded as UTF-8.
+    pub fn read_bytes_to_end(&mut self) -> Result<Vec<u8>, DecompressError> {
+        let mut output = Vec::new();
+        let mut buffer = [0; 4];
+        let mode = self.mode;
+
+        for c_result in self {
+            match c_result {
+                Ok(c) if mode == DecodeMode::Bytes => output.push(c as u8),
+                Ok(c)                              => output.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes()),
+                Err(err)                           => return Err(err),
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn start_repeat_directive(&mut self, open: DecompressToken) -> Option<Result<char, DecompressError>> {
+        match self.consume_repeat_directive(open) {
             Ok(())   => {},
             Err(err) => return self.error(err),
         };
@@ -78,47 +98,35 @@ impl Decompressor  {
             Err(err) => return self.error(err),
         };
 
+        // A directive that produces nothing, like (0x5) or (3x0), is skipped.
+        if self.repeat_sequence.is_empty() || self.repeat_count_remaining == 0 {
+            self.state = State::Initial;
+            return self.next();
+        }
+
         self.state = State::RepeatDirective;
         self.next_repeat_character()
     }
 
-    fn consume_repeat_directive(&mut self) -> Result<(), DecompressError> {
-        match (self.tokens.next(),
-               self.tokens.next(),
-               self.tokens.next(),
-               self.tokens.next()) {
-              (Some(Ok(DecompressToken { text: _, token_type: DecompressTokenType::Integer(length) })),
-               Some(Ok(DecompressToken { text: _, token_type: DecompressTokenType::X })),
-               Some(Ok(DecompressToken { text: _, token_type: DecompressTokenType::Integer(count) })),
-               Some(Ok(DecompressToken { text: _, token_type: DecompressTokenType::CloseParenthesis }))) => {
-                self.repeat_length_remaining = length;
-                self.repeat_count_remaining = count;
+    fn consume_repeat_directive(&mut self, open: DecompressToken) -> Result<(), DecompressError> {
+        match self.tokens.read_repeat_directive(open) {
+            Ok(directive) => {
+                self.repeat_length_remaining = directive.length;
+                self.repeat_count_remaining = directive.count;
                 Ok(())
-              },
-              _                                   => {
-                Err(DecompressError::InvalidRepeatDirective)
-              },
+            },
+            Err(err)      => Err(err),
         }
     }
 
     fn consume_repeat_sequence(&mut self) -> Result<(), DecompressError> {
-        self.repeat_sequence.clear();
-
-        let mut i = 0;
-        while i < self.repeat_length_remaining {            
-            match self.tokens.next() {
-                Some(Ok(DecompressToken { text, token_type: _ })) => {
-                    i += text.len();
-                    for c in text {
-                        self.repeat_sequence.push(c)
-                    }
-                },
-                _                                                 => {
-                    return Err(DecompressError::ExpectedMoreCharacters)
-                },
-            };
-        }
-        
+        // The repeated data is taken verbatim so that it may contain anything,
+        // including parentheses that don't form a valid marker.
+        self.repeat_sequence = match self.tokens.read_raw(self.repeat_length_remaining) {
+            Ok(repeat_sequence) => repeat_sequence,
+            Err(err)            => return Err(err),
+        };
+
         Ok(())
     }
 
@@ -177,10 +185,10 @@ impl Iterator for Decompressor {
                 };
 
                 match t {
-                    DecompressToken { text: _, token_type: DecompressTokenType::OpenParenthesis } => {
-                        return self.start_repeat_directive();
+                    DecompressToken { token_type: DecompressTokenType::OpenParenthesis, .. } => {
+                        return self.start_repeat_directive(t);
                     },
-                    DecompressToken { text, token_type: _ }                                       => {
+                    DecompressToken { text, .. }                                             => {
                         return self.text(text);
                     },
                 }
@@ -1,6 +1,7 @@
 use std::io;
-use std::iter::Peekable;
+use decodemode::DecodeMode;
 use decompresserror::DecompressError;
+use decompresserror::DecompressErrorKind;
 use filechars::FileChars;
 
 #[derive(Clone, Copy, Debug)]
@@ -16,17 +17,27 @@ pub enum DecompressTokenType {
 pub struct DecompressToken {
     pub text: Vec<char>,
     pub token_type: DecompressTokenType,
+    pub offset: usize,
 }
 
 impl DecompressToken {
-    fn new(text: Vec<char>, token_type: DecompressTokenType) -> DecompressToken {
+    fn new(text: Vec<char>, token_type: DecompressTokenType, offset: usize) -> DecompressToken {
         DecompressToken {
             text: text,
             token_type: token_type,
+            offset: offset,
         }
     }
 }
 
+/// A complete repeat directive, e.g. "(10x2)".
+#[derive(Debug)]
+pub struct RepeatDirective {
+    pub length: usize,
+    pub count: usize,
+    pub text: Vec<char>,
+}
+
 #[derive(Debug, PartialEq)]
 enum State {
     Initial,
@@ -38,47 +49,138 @@ enum State {
 }
 
 pub struct DecompressTokens {
-    chars: Peekable<FileChars>,
+    chars: FileChars,
     state: State,
+    marker: String,
 }
 
 impl DecompressTokens {
-    pub fn open(path: &str) -> Result<DecompressTokens, io::Error> {
-        let chars = match FileChars::open(path) {
+    pub fn open(path: &str, mode: DecodeMode) -> Result<DecompressTokens, io::Error> {
+        let chars = match FileChars::open(path, mode) {
             Ok(chars) => chars,
             Err(err)  => return Err(err),
         };
 
         Ok(DecompressTokens {
-            chars: chars.peekable(),
+            chars: chars,
             state: State::Initial,
+            marker: String::new(),
         })
     }
 
-    fn read_integer(&mut self, next_state: State) -> Result<DecompressToken, DecompressError> {        
+    /// The byte offset of the next character to be tokenized.
+    pub fn offset(&self) -> usize {
+        self.chars.offset()
+    }
+
+    /// Reads the rest of a repeat directive, given the open parenthesis token
+    /// that started it.
+    pub fn read_repeat_directive(&mut self, open: DecompressToken) -> Result<RepeatDirective, DecompressError> {
+        match (self.expect_token("integer"),
+               self.expect_token("'x'"),
+               self.expect_token("integer"),
+               self.expect_token("')'")) {
+              (Ok(DecompressToken { text: t1, token_type: DecompressTokenType::Integer(length), .. }),
+               Ok(DecompressToken { text: t2, token_type: DecompressTokenType::X, .. }),
+               Ok(DecompressToken { text: t3, token_type: DecompressTokenType::Integer(count), .. }),
+               Ok(DecompressToken { text: t4, token_type: DecompressTokenType::CloseParenthesis, .. })) => {
+                let mut text = open.text;
+                text.extend(t1);
+                text.extend(t2);
+                text.extend(t3);
+                text.extend(t4);
+
+                Ok(RepeatDirective {
+                    length: length,
+                    count: count,
+                    text: text,
+                })
+              },
+              (Err(err), _, _, _) |
+              (_, Err(err), _, _) |
+              (_, _, Err(err), _) |
+              (_, _, _, Err(err))                 => {
+                Err(err)
+              },
+              _                                   => {
+                Err(DecompressError::new(DecompressErrorKind::InvalidRepeatDirective, open.offset)
+                    .in_marker(&self.marker))
+              },
+        }
+    }
+
+    /// Reads the next `length` characters verbatim, without interpreting any
+    /// parentheses in them. This is how the data following a repeat directive
+    /// is consumed when markers should not be expanded recursively.
+    pub fn read_raw(&mut self, length: usize) -> Result<Vec<char>, DecompressError> {
+        let mut output = Vec::with_capacity(length);
+
+        while output.len() < length {
+            match self.chars.next() {
+                Some(Ok(c))    => output.push(c),
+                Some(Err(err)) => return Err(self.fail(err)),
+                None           => {
+                    let err = DecompressError::new(DecompressErrorKind::ExpectedMoreCharacters, self.chars.offset());
+                    return Err(self.fail(err));
+                },
+            };
+        }
+
+        Ok(output)
+    }
+
+    fn expect_token(&mut self, expected: &'static str) -> Result<DecompressToken, DecompressError> {
+        match self.next() {
+            Some(result) => result,
+            None         => {
+                Err(DecompressError::new(DecompressErrorKind::InvalidRepeatDirective, self.chars.offset())
+                    .in_marker(&self.marker)
+                    .expecting(expected))
+            },
+        }
+    }
+
+    fn read_integer(&mut self, next_state: State) -> Result<DecompressToken, DecompressError> {
+        let offset = self.chars.offset();
         match read_integer(&mut self.chars) {
             Ok((text, i))    => {
                 self.state = next_state;
-                Ok(DecompressToken::new(text, DecompressTokenType::Integer(i)))
+                self.marker.extend(text.iter());
+                Ok(DecompressToken::new(text, DecompressTokenType::Integer(i), offset))
             },
             Err(err) => {
-                self.state = State::Error;
-                Err(err)
+                Err(self.fail(err))
             }
         }
     }
 
     fn token(&mut self, c: char, next_state: State, token_type: DecompressTokenType) -> Result<DecompressToken, DecompressError> {
+        let offset = self.chars.offset();
         let text = vec![c];
         self.chars.next();
         self.state = next_state;
-        Ok(DecompressToken::new(text, token_type))
+
+        match token_type {
+            DecompressTokenType::Character(_)    => self.marker.clear(),
+            DecompressTokenType::OpenParenthesis => {
+                self.marker.clear();
+                self.marker.push(c);
+            },
+            _                                    => self.marker.push(c),
+        };
+
+        Ok(DecompressToken::new(text, token_type, offset))
     }
 
-    fn error(&mut self, err: DecompressError) -> Result<DecompressToken, DecompressError> {   
+    fn error(&mut self, kind: DecompressErrorKind) -> Result<DecompressToken, DecompressError> {
+        let err = DecompressError::new(kind, self.chars.offset());
         self.chars.next();
+        Err(self.fail(err))
+    }
+
+    fn fail(&mut self, err: DecompressError) -> DecompressError {
         self.state = State::Error;
-        Err(err)
+        err.in_marker(&self.marker)
     }
 }
 
@@ -86,10 +188,23 @@ impl Iterator for DecompressTokens {
     type Item = Result<DecompressToken, DecompressError>;
 
     fn next(&mut self) -> Option<Result<DecompressToken, DecompressError>> {
-        let c = match self.chars.peek() {
-            Some(&Ok(c))    => c,
-            Some(&Err(err)) => return Some(Err(err)),
-            None            => return None,
+        if self.state == State::Error {
+            return None;
+        }
+
+        let c = match self.chars.peek().cloned() {
+            Some(Ok(c))    => c,
+            Some(Err(err)) => return Some(Err(self.fail(err))),
+            None           => {
+                // The input must not end in the middle of a repeat directive.
+                return match self.state {
+                    State::ExpectingFirstInteger |
+                    State::ExpectingSecondInteger    => Some(self.error(DecompressErrorKind::ExpectedInteger)),
+                    State::ExpectingX                => Some(self.error(DecompressErrorKind::ExpectedX)),
+                    State::ExpectingCloseParenthesis => Some(self.error(DecompressErrorKind::ExpectedCloseParenthesis)),
+                    State::Initial | State::Error    => None,
+                };
+            },
         };
 
         let output = match self.state {
@@ -100,19 +215,19 @@ impl Iterator for DecompressTokens {
                 self.read_integer(State::ExpectingCloseParenthesis)
             },
             State::ExpectingFirstInteger | State::ExpectingSecondInteger => {
-                self.error(DecompressError::ExpectedInteger)
+                self.error(DecompressErrorKind::ExpectedInteger)
             },
             State::ExpectingX                if c == 'x'                 => {
                 self.token(c, State::ExpectingSecondInteger, DecompressTokenType::X)
             },
             State::ExpectingX                                            => {
-                self.error(DecompressError::ExpectedX)
+                self.error(DecompressErrorKind::ExpectedX)
             },
             State::ExpectingCloseParenthesis if c == ')'                 => {
                 self.token(c, State::Initial, DecompressTokenType::CloseParenthesis)
             },
             State::ExpectingCloseParenthesis                             => {
-                self.error(DecompressError::ExpectedCloseParenthesis)
+                self.error(DecompressErrorKind::ExpectedCloseParenthesis)
             },
             State::Initial if c == '('                                   => {
                 self.token(c, State::ExpectingFirstInteger, DecompressTokenType::OpenParenthesis)
@@ -133,7 +248,8 @@ fn is_digit(c: char) -> bool {
     c.is_digit(10)
 }
 
-fn read_integer(chars: &mut Peekable<FileChars>) -> Result<(Vec<char>, usize), DecompressError> {
+fn read_integer(chars: &mut FileChars) -> Result<(Vec<char>, usize), DecompressError> {
+    let offset = chars.offset();
     let unparsed_integer: String = match take_while(chars, |l, c| l <= 10 && is_digit(c)) {
         Ok(v)    => v.into_iter().collect(),
         Err(err) => return Err(err),
@@ -141,28 +257,29 @@ fn read_integer(chars: &mut Peekable<FileChars>) -> Result<(Vec<char>, usize), D
 
     match unparsed_integer.parse::<usize>() {
         Ok(i)  => Ok((unparsed_integer.chars().collect(), i)),
-        Err(_) => Err(DecompressError::CouldNotParseInteger),
+        Err(_) => Err(DecompressError::new(DecompressErrorKind::CouldNotParseInteger, offset)),
     }
 }
 
-fn take_while<F>(chars: &mut Peekable<FileChars>, condition: F) -> Result<Vec<char>, DecompressError>
+fn take_while<F>(chars: &mut FileChars, condition: F) -> Result<Vec<char>, DecompressError>
     where F : Fn(usize, char) -> bool {
 
     let mut output = Vec::new();
 
-    while let Some(&c) = chars.peek() {
-        match c {
+    while let Some(c) = chars.peek() {
+        match *c {
             Ok(c) if condition(output.len(), c) => {
                 output.push(c);
-                chars.next();
             },
             Ok(_)                   => {
                 break;
             }
-            Err(err)                => {
-                return Err(err);
+            Err(ref err)            => {
+                return Err(err.clone());
             },
         };
+
+        chars.next();
     }
 
     Ok(output)
@@ -0,0 +1,277 @@
+use std::fmt::Write;
+use std::io;
+use decodemode::DecodeMode;
+use decompresserror::DecompressError;
+use decompresserror::DecompressErrorKind;
+use decompresstokens::DecompressTokens;
+use decompresstokens::DecompressToken;
+use decompresstokens::DecompressTokenType;
+
+/// A node in the version two expansion of the input. Repeat nodes own the
+/// nodes that make up the data they repeat, so nested markers become nested
+/// nodes.
+#[derive(Debug)]
+pub enum ExpansionNode {
+    Text {
+        text: String,
+        offset: usize,
+    },
+    Repeat {
+        marker: String,
+        offset: usize,
+        length: usize,
+        count: usize,
+        children: Vec<ExpansionNode>,
+        expanded_length: u64,
+    },
+}
+
+impl ExpansionNode {
+    pub fn expanded_length(&self) -> u64 {
+        match *self {
+            ExpansionNode::Text { ref text, .. }          => text.chars().count() as u64,
+            ExpansionNode::Repeat { expanded_length, .. } => expanded_length,
+        }
+    }
+
+    fn pretty_print(&self, depth: usize, output: &mut String) {
+        for _ in 0..depth {
+            output.push_str("  ");
+        }
+
+        match *self {
+            ExpansionNode::Text { ref text, offset } => {
+                writeln!(output, "text {:?} at byte {}: {}", text, offset, self.expanded_length()).unwrap();
+            },
+            ExpansionNode::Repeat { ref marker, offset, ref children, expanded_length, .. } => {
+                writeln!(output, "repeat {} at byte {}: {}", marker, offset, expanded_length).unwrap();
+                for child in children {
+                    child.pretty_print(depth + 1, output);
+                }
+            },
+        }
+    }
+
+    fn write_json(&self, output: &mut String) {
+        match *self {
+            ExpansionNode::Text { ref text, offset } => {
+                output.push_str("{\"type\":\"text\",\"text\":");
+                write_json_string(text, output);
+                write!(output, ",\"offset\":{},\"expanded_length\":{}}}", offset, self.expanded_length()).unwrap();
+            },
+            ExpansionNode::Repeat { ref marker, offset, length, count, ref children, expanded_length } => {
+                output.push_str("{\"type\":\"repeat\",\"marker\":");
+                write_json_string(marker, output);
+                write!(output, ",\"offset\":{},\"length\":{},\"count\":{},\"expanded_length\":{},\"children\":",
+                       offset, length, count, expanded_length).unwrap();
+                write_json_array(children, output);
+                output.push('}');
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ExpansionTree {
+    pub nodes: Vec<ExpansionNode>,
+}
+
+impl ExpansionTree {
+    pub fn expanded_length(&self) -> u64 {
+        self.nodes.iter().map(|n| n.expanded_length()).sum()
+    }
+
+    /// Renders one node per line, indented by depth, with the expanded length
+    /// of every node.
+    pub fn pretty_print(&self) -> String {
+        let mut output = String::new();
+        writeln!(output, "total: {}", self.expanded_length()).unwrap();
+        for node in &self.nodes {
+            node.pretty_print(1, &mut output);
+        }
+
+        output
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut output = String::new();
+        write!(output, "{{\"expanded_length\":{},\"children\":", self.expanded_length()).unwrap();
+        write_json_array(&self.nodes, &mut output);
+        output.push('}');
+
+        output
+    }
+}
+
+pub struct ExpansionTreeParser {
+    tokens: DecompressTokens,
+    repeat_sequences: Vec<RepeatSequence>,
+    nodes: Vec<ExpansionNode>,
+}
+
+#[derive(Debug)]
+struct RepeatSequence {
+    marker: String,
+    offset: usize,
+    repeat_length: usize,
+    repeat_count: usize,
+    repeat_length_remaining: usize,
+    children: Vec<ExpansionNode>,
+}
+
+impl ExpansionTreeParser {
+    pub fn open(path: &str, mode: DecodeMode) -> Result<ExpansionTreeParser, io::Error> {
+        let tokens = match DecompressTokens::open(path, mode) {
+            Ok(tokens) => tokens,
+            Err(err)   => return Err(err),
+        };
+
+        Ok(ExpansionTreeParser {
+            tokens: tokens,
+            repeat_sequences: Vec::new(),
+            nodes: Vec::new(),
+        })
+    }
+
+    pub fn parse(mut self) -> Result<ExpansionTree, DecompressError> {
+        loop {
+            let token = match self.tokens.next() {
+                Some(Ok(token)) => token,
+                Some(Err(err))  => return Err(err),
+                None            => break,
+            };
+
+            match token {
+                DecompressToken { token_type: DecompressTokenType::OpenParenthesis, offset, .. } => {
+                    let directive = match self.tokens.read_repeat_directive(token) {
+                        Ok(directive) => directive,
+                        Err(err)      => return Err(err),
+                    };
+
+                    let marker: String = directive.text.into_iter().collect();
+                    let marker_length = marker.chars().count();
+                    if !self.subtract_text_length(marker_length) || !self.fits(directive.length) {
+                        return Err(DecompressError::new(DecompressErrorKind::InvalidRepeatDirective, offset)
+                            .in_marker(&marker));
+                    }
+
+                    self.repeat_sequences.push(RepeatSequence {
+                        marker: marker,
+                        offset: offset,
+                        repeat_length: directive.length,
+                        repeat_count: directive.count,
+                        repeat_length_remaining: directive.length,
+                        children: Vec::new(),
+                    });
+                },
+                DecompressToken { text, offset, .. }                                             => {
+                    // Text tokens are single characters, so consecutive ones
+                    // are merged into one node.
+                    let length = text.len();
+                    self.add_text(text, offset);
+                    self.subtract_text_length(length);
+                },
+            }
+
+            self.pop_completed();
+        }
+
+        // Any sequence still open ran past the end of the input.
+        if let Some(incomplete) = self.repeat_sequences.last() {
+            return Err(DecompressError::new(DecompressErrorKind::ExpectedMoreCharacters, self.tokens.offset())
+                .in_marker(&incomplete.marker));
+        }
+
+        Ok(ExpansionTree {
+            nodes: self.nodes,
+        })
+    }
+
+    fn current_nodes(&mut self) -> &mut Vec<ExpansionNode> {
+        match self.repeat_sequences.last_mut() {
+            Some(last) => &mut last.children,
+            None       => &mut self.nodes,
+        }
+    }
+
+    fn add_text(&mut self, text: Vec<char>, offset: usize) {
+        let nodes = self.current_nodes();
+        if let Some(&mut ExpansionNode::Text { text: ref mut previous, .. }) = nodes.last_mut() {
+            previous.extend(text);
+            return;
+        }
+
+        nodes.push(ExpansionNode::Text {
+            text: text.into_iter().collect(),
+            offset: offset,
+        });
+    }
+
+    /// Whether data of the given length fits in every open sequence.
+    fn fits(&self, length: usize) -> bool {
+        self.repeat_sequences.iter().all(|s| s.repeat_length_remaining >= length)
+    }
+
+    /// Returns false if the text doesn't fit in one of the open sequences,
+    /// meaning a marker straddles the end of the data it is nested in.
+    fn subtract_text_length(&mut self, length: usize) -> bool {
+        for repeat_sequence in self.repeat_sequences.iter_mut() {
+            if repeat_sequence.repeat_length_remaining < length {
+                return false;
+            }
+
+            repeat_sequence.repeat_length_remaining -= length;
+        }
+
+        true
+    }
+
+    fn pop_completed(&mut self) {
+        while let Some(remaining) = self.repeat_sequences.last().map(|s| s.repeat_length_remaining) {
+            if remaining > 0 {
+                return;
+            }
+
+            let completed = self.repeat_sequences.pop().unwrap();
+            let sum: u64 = completed.children.iter().map(|n| n.expanded_length()).sum();
+            let node = ExpansionNode::Repeat {
+                marker: completed.marker,
+                offset: completed.offset,
+                length: completed.repeat_length,
+                count: completed.repeat_count,
+                children: completed.children,
+                expanded_length: sum * (completed.repeat_count as u64),
+            };
+
+            self.current_nodes().push(node);
+        }
+    }
+}
+
+fn write_json_array(nodes: &[ExpansionNode], output: &mut String) {
+    output.push('[');
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+
+        node.write_json(output);
+    }
+    output.push(']');
+}
+
+fn write_json_string(value: &str, output: &mut String) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"'                 => output.push_str("\\\""),
+            '\\'                => output.push_str("\\\\"),
+            '\n'                => output.push_str("\\n"),
+            '\r'                => output.push_str("\\r"),
+            '\t'                => output.push_str("\\t"),
+            c if c < ' '        => write!(output, "\\u{:04x}", c as u32).unwrap(),
+            c                   => output.push(c),
+        };
+    }
+    output.push('"');
+}
@@ -3,14 +3,20 @@ use std::io::BufReader;
 use std::io::Bytes;
 use std::io::Read;
 use std::io;
+use std::str;
+use decodemode::DecodeMode;
 use decompresserror::DecompressError;
+use decompresserror::DecompressErrorKind;
 
 pub struct FileChars {
     bytes: Bytes<BufReader<File>>,
+    mode: DecodeMode,
+    offset: usize,
+    peeked: Option<(usize, Option<Result<char, DecompressError>>)>,
 }
 
 impl FileChars {
-    pub fn open(path: &str) -> Result<FileChars, io::Error> {
+    pub fn open(path: &str, mode: DecodeMode) -> Result<FileChars, io::Error> {
         let file = match File::open(path) {
             Ok(file) => file,
             Err(err)   => return Err(err),
@@ -21,19 +27,96 @@ impl FileChars {
 
         Ok(FileChars {
             bytes: bytes,
+            mode: mode,
+            offset: 0,
+            peeked: None,
         })
-    } 
+    }
+
+    /// The byte offset of the next character that will be returned, or the
+    /// length of the input once every character has been read.
+    pub fn offset(&self) -> usize {
+        match self.peeked {
+            Some((offset, _)) => offset,
+            None              => self.offset,
+        }
+    }
+
+    pub fn peek(&mut self) -> Option<&Result<char, DecompressError>> {
+        if self.peeked.is_none() {
+            let offset = self.offset;
+            let next = self.read_char();
+            self.peeked = Some((offset, next));
+        }
+
+        match self.peeked {
+            Some((_, ref next)) => next.as_ref(),
+            None                => None,
+        }
+    }
+
+    fn read_byte(&mut self) -> Option<Result<u8, DecompressError>> {
+        match self.bytes.next() {
+            Some(Ok(b))  => {
+                self.offset += 1;
+                Some(Ok(b))
+            },
+            None         => None,
+            Some(Err(_)) => Some(Err(DecompressError::new(DecompressErrorKind::CouldNotReadFile, self.offset))),
+        }
+    }
+
+    fn read_char(&mut self) -> Option<Result<char, DecompressError>> {
+        let offset = self.offset;
+        let b = match self.read_byte() {
+            Some(Ok(b))    => b,
+            Some(Err(err)) => return Some(Err(err)),
+            None           => return None,
+        };
+
+        match self.mode {
+            DecodeMode::Ascii if b < 128 => Some(Ok(b as char)),
+            DecodeMode::Ascii            => Some(Err(DecompressError::new(DecompressErrorKind::NonAsciiCharEncountered, offset))),
+            DecodeMode::Bytes            => Some(Ok(b as char)),
+            DecodeMode::Utf8             => Some(self.read_utf8(offset, b)),
+        }
+    }
+
+    fn read_utf8(&mut self, offset: usize, first: u8) -> Result<char, DecompressError> {
+        let invalid = DecompressError::new(DecompressErrorKind::InvalidUtf8Sequence, offset);
+        let width = match first {
+            0x00..=0x7F => 1,
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _           => return Err(invalid),
+        };
+
+        let mut buffer = [first, 0, 0, 0];
+        for b in buffer.iter_mut().take(width).skip(1) {
+            *b = match self.read_byte() {
+                Some(Ok(b))    => b,
+                Some(Err(err)) => return Err(err),
+                None           => return Err(invalid),
+            };
+        }
+
+        // The standard library does the validation of overlong encodings and
+        // surrogates, which a check of the leading byte alone can't catch.
+        match str::from_utf8(&buffer[..width]) {
+            Ok(s)  => Ok(s.chars().next().unwrap()),
+            Err(_) => Err(invalid),
+        }
+    }
 }
 
 impl Iterator for FileChars {
     type Item = Result<char, DecompressError>;
 
     fn next(&mut self) -> Option<Result<char, DecompressError>> {
-        match self.bytes.next() {
-            Some(Ok(b)) if b < 128 => Some(Ok(b as char)),
-            None                   => None,
-            Some(Ok(_))            => Some(Err(DecompressError::NonAsciiCharEncountered)),
-            Some(Err(_))           => Some(Err(DecompressError::CouldNotReadFile)),
+        match self.peeked.take() {
+            Some((_, next)) => next,
+            None            => self.read_char(),
         }
     }
 }
@@ -1,24 +1,131 @@
+mod decodemode;
 mod decompresserror;
 mod filechars;
 mod decompresstokens;
 mod decompressor;
 mod recursivedecompressor;
+mod expansiontree;
 
+use std::env;
+use std::io;
+use std::io::Write;
+use std::process;
+use decodemode::DecodeMode;
 use decompressor::Decompressor;
+use expansiontree::ExpansionTreeParser;
 use recursivedecompressor::RecursiveDecompressor;
 
+const USAGE: &'static str = "usage: day9 [--mode ascii|bytes|utf8] [--decompress | --tree | --json] [path]";
+
+struct Options {
+    path: String,
+    mode: DecodeMode,
+    output: Output,
+}
+
+/// What to print instead of the two puzzle answers.
+enum Output {
+    Answers,
+    Decompressed,
+    Tree,
+    Json,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        path: "input.txt".to_string(),
+        mode: DecodeMode::Ascii,
+        output: Output::Answers,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--mode"                   => {
+                options.mode = match args.next().map(|s| &s[..]) {
+                    Some("ascii") => DecodeMode::Ascii,
+                    Some("bytes") => DecodeMode::Bytes,
+                    Some("utf8")  => DecodeMode::Utf8,
+                    Some(other)   => return Err(format!("Unknown decode mode '{}'.", other)),
+                    None          => return Err("Expected a decode mode after --mode.".to_string()),
+                };
+            },
+            "--decompress"             => options.output = Output::Decompressed,
+            "--tree"                   => options.output = Output::Tree,
+            "--json"                   => options.output = Output::Json,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'.", arg)),
+            _                          => options.path = arg.clone(),
+        };
+    }
+
+    Ok(options)
+}
+
+/// Writes the version one decompression of the input to stdout. In the byte
+/// mode the output is the raw bytes, so binary payloads survive a round trip.
+fn write_decompressed(options: &Options) -> Result<(), String> {
+    let mut decompressor = Decompressor::open(&options.path, options.mode).map_err(|err| err.to_string())?;
+
+    let output = if options.mode == DecodeMode::Bytes {
+        decompressor.read_bytes_to_end()
+    } else {
+        decompressor.read_to_end().map(|s| s.into_bytes())
+    };
+
+    let output = output.map_err(|err| err.to_string())?;
+    io::stdout().write_all(&output).map_err(|err| err.to_string())
+}
+
+/// Prints the version two marker tree, as indented text or as JSON.
+fn write_tree(options: &Options) -> Result<(), String> {
+    let tree = ExpansionTreeParser::open(&options.path, options.mode)
+        .map_err(|err| err.to_string())?
+        .parse()
+        .map_err(|err| err.to_string())?;
+
+    match options.output {
+        Output::Json => println!("{}", tree.to_json()),
+        _            => print!("{}", tree.pretty_print()),
+    };
+
+    Ok(())
+}
+
 fn main() {
-    let path = "input.txt";
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Ok(options) => options,
+        Err(err)    => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    let result = match options.output {
+        Output::Answers      => print_answers(&options),
+        Output::Decompressed => write_decompressed(&options),
+        Output::Tree         => write_tree(&options),
+        Output::Json         => write_tree(&options),
+    };
 
-    let part_1_result = Decompressor::open(path)
-        .unwrap()
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn print_answers(options: &Options) -> Result<(), String> {
+    let part_1_result = Decompressor::open(&options.path, options.mode)
+        .map_err(|err| err.to_string())?
         .len()
-        .unwrap();
+        .map_err(|err| err.to_string())?;
     println!("Part 1 result: {}", part_1_result);
 
-    let part_2_result = RecursiveDecompressor::open(path)
-        .unwrap()
+    let part_2_result = RecursiveDecompressor::open(&options.path, options.mode)
+        .map_err(|err| err.to_string())?
         .len()
-        .unwrap();
+        .map_err(|err| err.to_string())?;
     println!("Part 2 result: {}", part_2_result);
+
+    Ok(())
 }
@@ -1,5 +1,7 @@
 use std::io;
+use decodemode::DecodeMode;
 use decompresserror::DecompressError;
+use decompresserror::DecompressErrorKind;
 use decompresstokens::DecompressTokens;
 use decompresstokens::DecompressToken;
 use decompresstokens::DecompressTokenType;
@@ -16,12 +18,13 @@ struct RepeatSequence {
     repeat_count: usize,
     repeat_length_remaining: usize,
     text_length: usize,
+    marker: String,
     sum: u64,
 }
 
 impl RecursiveDecompressor {
-    pub fn open(path: &str) -> Result<RecursiveDecompressor, io::Error> {
-        let tokens = match DecompressTokens::open(path) {
+    pub fn open(path: &str, mode: DecodeMode) -> Result<RecursiveDecompressor, io::Error> {
+        let tokens = match DecompressTokens::open(path, mode) {
             Ok(tokens) => tokens,
             Err(err)   => return Err(err),
         };
@@ -33,26 +36,19 @@ impl RecursiveDecompressor {
         })
     }
 
-    fn consume_repeat_sequence(&mut self, t0_length: usize) -> Result<RepeatSequence, DecompressError> {
-        match (self.tokens.next(),
-               self.tokens.next(),
-               self.tokens.next(),
-               self.tokens.next()) {
-              (Some(Ok(DecompressToken { text: t1, token_type: DecompressTokenType::Integer(length) })),
-               Some(Ok(DecompressToken { text: t2, token_type: DecompressTokenType::X })),
-               Some(Ok(DecompressToken { text: t3, token_type: DecompressTokenType::Integer(count) })),
-               Some(Ok(DecompressToken { text: t4, token_type: DecompressTokenType::CloseParenthesis }))) => {
+    fn consume_repeat_sequence(&mut self, open: DecompressToken) -> Result<RepeatSequence, DecompressError> {
+        match self.tokens.read_repeat_directive(open) {
+            Ok(directive) => {
                 Ok(RepeatSequence {
-                    text_length: t0_length + t1.len() + t2.len() + t3.len() + t4.len(),
-                    repeat_length: length,
-                    repeat_count: count,
-                    repeat_length_remaining: length,
+                    text_length: directive.text.len(),
+                    marker: directive.text.into_iter().collect(),
+                    repeat_length: directive.length,
+                    repeat_count: directive.count,
+                    repeat_length_remaining: directive.length,
                     sum: 0,
                 })
-              },
-              _                                   => {
-                Err(DecompressError::InvalidRepeatDirective)
-              },
+            },
+            Err(err)      => Err(err),
         }
     }
 
@@ -75,10 +71,23 @@ impl RecursiveDecompressor {
         };
     }
 
-    fn subtract_text_length(&mut self, length: usize) {
+    /// Returns false if the text doesn't fit in one of the open sequences,
+    /// meaning a marker straddles the end of the data it is nested in.
+    fn subtract_text_length(&mut self, length: usize) -> bool {
         for repeat_sequence in self.repeat_sequences.iter_mut() {
+            if repeat_sequence.repeat_length_remaining < length {
+                return false;
+            }
+
             repeat_sequence.repeat_length_remaining -= length;
         }
+
+        true
+    }
+
+    /// Whether data of the given length fits in every open sequence.
+    fn fits(&self, length: usize) -> bool {
+        self.repeat_sequences.iter().all(|s| s.repeat_length_remaining >= length)
     }
 
     pub fn len(mut self) -> Result<u64, DecompressError> {
@@ -90,20 +99,31 @@ impl RecursiveDecompressor {
             };
 
             match token {
-                DecompressToken { text: t0, token_type: DecompressTokenType::OpenParenthesis } => {
-                    let repeat_sequence = match self.consume_repeat_sequence(t0.len()) {
+                DecompressToken { token_type: DecompressTokenType::OpenParenthesis, offset, .. } => {
+                    let repeat_sequence = match self.consume_repeat_sequence(token) {
                         Ok(repeat_sequence) => repeat_sequence,
                         Err(err)            => return Err(err),
                     };
 
-                    self.subtract_text_length(repeat_sequence.text_length);
+                    if !self.subtract_text_length(repeat_sequence.text_length) || !self.fits(repeat_sequence.repeat_length) {
+                        return Err(DecompressError::new(DecompressErrorKind::InvalidRepeatDirective, offset)
+                            .in_marker(&repeat_sequence.marker));
+                    }
 
                     self.repeat_sequences.push(repeat_sequence);
+
+                    // A zero length directive, like (0x3), is complete as soon
+                    // as it is read.
+                    self.pop_and_add();
                 },
-                DecompressToken { text, token_type: _ }                                        => {
+                DecompressToken { text, offset, .. }                                                     => {
                     self.add_value(text.len() as u64);
 
-                    self.subtract_text_length(text.len());
+                    if !self.subtract_text_length(text.len()) {
+                        let marker = self.repeat_sequences.last().map(|s| s.marker.clone()).unwrap_or_default();
+                        return Err(DecompressError::new(DecompressErrorKind::InvalidRepeatDirective, offset)
+                            .in_marker(&marker));
+                    }
 
                     let should_pop = match self.repeat_sequences.last() {
                         Some(s) if s.repeat_length_remaining == 0 => true,
@@ -119,6 +139,12 @@ impl RecursiveDecompressor {
 
         self.pop_and_add();
 
+        // Any sequence still open ran past the end of the input.
+        if let Some(incomplete) = self.repeat_sequences.last() {
+            return Err(DecompressError::new(DecompressErrorKind::ExpectedMoreCharacters, self.tokens.offset())
+                .in_marker(&incomplete.marker));
+        }
+
         Ok(self.sum)
     }
 }
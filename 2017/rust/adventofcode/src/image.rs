@@ -0,0 +1,162 @@
+//! Saves images as PBM and PGM files, or as PNG files with the "png" feature.
+//!
+//! 2015/rust/day6, 2016/rust/day8 and 2017/rust/adventofcode each have an
+//! identical copy of this file, so that every year still builds on its own.
+//! Change all three together.
+
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+
+/// An image where every pixel is either on or off. On pixels are drawn black.
+pub struct BitmapImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+/// An image where every pixel is a shade of gray, from 0 (black) to 255 (white).
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl BitmapImage {
+    /// Builds the image by asking for the pixel at each column x and row y.
+    pub fn from_fn<F>(width: usize, height: usize, pixel: F) -> BitmapImage
+        where F : Fn(usize, usize) -> bool {
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(pixel(x, y));
+            }
+        }
+
+        BitmapImage {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+
+    /// Writes a binary PBM (P4), where each row is packed into bytes.
+    pub fn write_pbm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "P4\n{} {}\n", self.width, self.height)?;
+
+        let mut row = vec![0u8; (self.width + 7) / 8];
+        for y in 0..self.height {
+            for b in row.iter_mut() {
+                *b = 0;
+            }
+
+            for x in 0..self.width {
+                if self.pixels[y * self.width + x] {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+
+            writer.write_all(&row)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn save_pbm(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_pbm(&mut writer)
+    }
+
+    /// Saves a PNG if the path ends with ".png" and a PBM otherwise.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        match is_png_path(path) {
+            true  => self.to_gray().save_png(path),
+            false => self.save_pbm(path),
+        }
+    }
+
+    pub fn to_gray(&self) -> GrayImage {
+        GrayImage {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|&p| if p { 0 } else { 255 }).collect(),
+        }
+    }
+
+}
+
+impl GrayImage {
+    /// Builds the image by asking for the pixel at each column x and row y.
+    pub fn from_fn<F>(width: usize, height: usize, pixel: F) -> GrayImage
+        where F : Fn(usize, usize) -> u8 {
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                pixels.push(pixel(x, y));
+            }
+        }
+
+        GrayImage {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+
+    /// Builds the image from row-major intensities, scaled so that zero (or
+    /// less) is black and the largest value is white.
+    pub fn from_intensities(width: usize, height: usize, values: &[i32]) -> GrayImage {
+        let max_value = values.iter().cloned().max().unwrap_or(0);
+
+        GrayImage::from_fn(width, height, |x, y| {
+            let value = values[y * width + x];
+            if value <= 0 || max_value <= 0 {
+                0
+            } else {
+                ((value as i64 * 255) / max_value as i64) as u8
+            }
+        })
+    }
+
+    /// Writes a binary PGM (P5) with one byte per pixel.
+    pub fn write_pgm<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)
+    }
+
+    pub fn save_pgm(&self, path: &str) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_pgm(&mut writer)
+    }
+
+    /// Saves a PNG if the path ends with ".png" and a PGM otherwise.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        match is_png_path(path) {
+            true  => self.save_png(path),
+            false => self.save_pgm(path),
+        }
+    }
+
+    #[cfg(feature = "png")]
+    pub fn save_png(&self, path: &str) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        writer.write_image_data(&self.pixels).map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    #[cfg(not(feature = "png"))]
+    pub fn save_png(&self, _path: &str) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Other, "PNG support requires the \"png\" feature"))
+    }
+}
+
+fn is_png_path(path: &str) -> bool {
+    path.to_lowercase().ends_with(".png")
+}
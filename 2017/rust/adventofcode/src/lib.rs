@@ -1 +1,5 @@
+#[cfg(feature = "png")]
+extern crate png;
+
 pub mod day10;
+pub mod image;
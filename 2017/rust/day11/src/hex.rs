@@ -0,0 +1,392 @@
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    North,
+    Northeast,
+    Southeast,
+    South,
+    Southwest,
+    Northwest,
+}
+
+/// Every direction, clockwise from north.
+pub const DIRECTIONS: [Direction; 6] = [
+    Direction::North,
+    Direction::Northeast,
+    Direction::Southeast,
+    Direction::South,
+    Direction::Southwest,
+    Direction::Northwest,
+];
+
+/// A hex in cube coordinates, where x + y + z = 0. North increases y and
+/// decreases z, and northeast increases x and decreases z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cube {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+/// A hex in axial coordinates, which are the cube's x and z. The row r grows
+/// towards the south.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Axial {
+    pub q: i32,
+    pub r: i32,
+}
+
+/// A hex in offset coordinates, where odd columns are pushed half a hex to
+/// the south and the row grows towards the south.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Offset {
+    pub col: i32,
+    pub row: i32,
+}
+
+impl Direction {
+    /// Parses a direction as written in the puzzle input, such as "n" or
+    /// "sw".
+    pub fn parse(input: &str) -> Option<Direction> {
+        match input {
+            "n"  => Some(Direction::North),
+            "ne" => Some(Direction::Northeast),
+            "se" => Some(Direction::Southeast),
+            "s"  => Some(Direction::South),
+            "sw" => Some(Direction::Southwest),
+            "nw" => Some(Direction::Northwest),
+            _    => None,
+        }
+    }
+
+    /// The change in cube coordinates for one step in this direction.
+    pub fn to_cube(self) -> Cube {
+        match self {
+            Direction::North     => Cube { x:  0, y:  1, z: -1 },
+            Direction::Northeast => Cube { x:  1, y:  0, z: -1 },
+            Direction::Southeast => Cube { x:  1, y: -1, z:  0 },
+            Direction::South     => Cube { x:  0, y: -1, z:  1 },
+            Direction::Southwest => Cube { x: -1, y:  0, z:  1 },
+            Direction::Northwest => Cube { x: -1, y:  1, z:  0 },
+        }
+    }
+
+    pub fn opposite(&self) -> Direction {
+        match *self {
+            Direction::North     => Direction::South,
+            Direction::Northeast => Direction::Southwest,
+            Direction::Southeast => Direction::Northwest,
+            Direction::South     => Direction::North,
+            Direction::Southwest => Direction::Northeast,
+            Direction::Northwest => Direction::Southeast,
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Direction::North     => "n",
+            Direction::Northeast => "ne",
+            Direction::Southeast => "se",
+            Direction::South     => "s",
+            Direction::Southwest => "sw",
+            Direction::Northwest => "nw",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl Cube {
+    pub fn origin() -> Cube {
+        Cube { x: 0, y: 0, z: 0 }
+    }
+
+    pub fn step(&self, direction: Direction) -> Cube {
+        self.add(direction.to_cube())
+    }
+
+    pub fn add(&self, other: Cube) -> Cube {
+        Cube {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+
+    pub fn scale(&self, factor: i32) -> Cube {
+        Cube {
+            x: self.x * factor,
+            y: self.y * factor,
+            z: self.z * factor,
+        }
+    }
+
+    /// The fewest steps between the two hexes.
+    pub fn distance_to(&self, other: &Cube) -> i32 {
+        ((self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()) / 2
+    }
+
+    pub fn distance_to_origin(&self) -> i32 {
+        self.distance_to(&Cube::origin())
+    }
+
+    /// The six hexes one step away, in the order of `DIRECTIONS`.
+    pub fn neighbours(&self) -> Vec<(Direction, Cube)> {
+        DIRECTIONS.iter().map(|&d| (d, self.step(d))).collect()
+    }
+
+    /// Every hex exactly `radius` steps away, clockwise from the hex that is
+    /// `radius` steps to the north.
+    pub fn ring(&self, radius: i32) -> Vec<Cube> {
+        if radius <= 0 {
+            return vec![*self];
+        }
+
+        let mut hexes = Vec::new();
+        let mut current = self.add(Direction::North.to_cube().scale(radius));
+
+        // Going clockwise from the north corner, the sides run southeast,
+        // south, southwest, northwest, north and northeast.
+        for &direction in DIRECTIONS.iter().cycle().skip(2).take(6) {
+            for _ in 0..radius {
+                hexes.push(current);
+                current = current.step(direction);
+            }
+        }
+
+        hexes
+    }
+
+    /// The hexes on a straight line from this hex to the other, including
+    /// both ends. Each hex of the line is a neighbour of the one before.
+    pub fn line_to(&self, other: &Cube) -> Vec<Cube> {
+        let distance = self.distance_to(other);
+        if distance == 0 {
+            return vec![*self];
+        }
+
+        // Nudge the ends so that points exactly between two hexes always
+        // round the same way.
+        let start = (self.x as f64 + 1e-6, self.y as f64 + 1e-6, self.z as f64 - 2e-6);
+        let end = (other.x as f64 + 1e-6, other.y as f64 + 1e-6, other.z as f64 - 2e-6);
+
+        (0..distance + 1)
+            .map(|i| {
+                let t = i as f64 / distance as f64;
+                round_cube(
+                    start.0 + (end.0 - start.0) * t,
+                    start.1 + (end.1 - start.1) * t,
+                    start.2 + (end.2 - start.2) * t)
+            })
+            .collect()
+    }
+
+    /// One of the shortest lists of steps from this hex to the other.
+    pub fn path_to(&self, other: &Cube) -> Vec<Direction> {
+        let mut path = Vec::new();
+        let mut current = *self;
+
+        while current != *other {
+            let distance = current.distance_to(other);
+            let (direction, next) = current
+                .neighbours()
+                .into_iter()
+                .find(|&(_, next)| next.distance_to(other) < distance)
+                .unwrap();
+
+            path.push(direction);
+            current = next;
+        }
+
+        path
+    }
+
+    /// One of the shortest lists of steps from this hex back to the origin.
+    pub fn path_to_origin(&self) -> Vec<Direction> {
+        self.path_to(&Cube::origin())
+    }
+
+    pub fn to_axial(self) -> Axial {
+        Axial {
+            q: self.x,
+            r: self.z,
+        }
+    }
+
+    pub fn to_offset(self) -> Offset {
+        self.to_axial().to_offset()
+    }
+}
+
+impl Axial {
+    pub fn to_cube(self) -> Cube {
+        Cube {
+            x: self.q,
+            y: -self.q - self.r,
+            z: self.r,
+        }
+    }
+
+    pub fn to_offset(self) -> Offset {
+        Offset {
+            col: self.q,
+            row: self.r + (self.q - (self.q & 1)) / 2,
+        }
+    }
+}
+
+impl Offset {
+    pub fn to_axial(self) -> Axial {
+        Axial {
+            q: self.col,
+            r: self.row - (self.col - (self.col & 1)) / 2,
+        }
+    }
+
+    pub fn to_cube(self) -> Cube {
+        self.to_axial().to_cube()
+    }
+}
+
+/// The hex nearest to a point in cube coordinates.
+fn round_cube(x: f64, y: f64, z: f64) -> Cube {
+    let mut rx = x.round();
+    let mut ry = y.round();
+    let mut rz = z.round();
+
+    let dx = (rx - x).abs();
+    let dy = (ry - y).abs();
+    let dz = (rz - z).abs();
+
+    // Fix whichever coordinate was rounded the most so the sum stays zero.
+    if dx > dy && dx > dz {
+        rx = -ry - rz;
+    } else if dy > dz {
+        ry = -rx - rz;
+    } else {
+        rz = -rx - ry;
+    }
+
+    Cube {
+        x: rx as i32,
+        y: ry as i32,
+        z: rz as i32,
+    }
+}
+
+/// Parses comma separated directions, allowing whitespace around each one.
+/// Returns the first piece that is not a direction.
+pub fn parse_directions(input: &str) -> Result<Vec<Direction>, String> {
+    input
+        .trim()
+        .split(',')
+        .map(|p| p.trim())
+        .map(|p| Direction::parse(p).ok_or_else(|| p.to_string()))
+        .collect()
+}
+
+/// Every hex visited while following the directions from the origin,
+/// starting with the origin itself.
+pub fn walk(directions: &[Direction]) -> Vec<Cube> {
+    let mut hexes = vec![Cube::origin()];
+    let mut current = Cube::origin();
+
+    for &direction in directions {
+        current = current.step(direction);
+        hexes.push(current);
+    }
+
+    hexes
+}
+
+/// Draws the hexes of a walk, north at the top. Each column of hexes is one
+/// character wide and each hex is two lines tall, with neighbouring columns
+/// shifted by one line. The origin is 'O', the end is 'E', other visited
+/// hexes are '#' and unvisited hexes are '.'.
+pub fn render_path(path: &[Cube]) -> String {
+    let mut output = String::new();
+
+    let (first, last) = match (path.first(), path.last()) {
+        (Some(first), Some(last)) => (*first, *last),
+        _                         => return output,
+    };
+
+    // In doubled coordinates a hex is at column q and line 2r + q, which
+    // puts every hex on its own line and column.
+    let visited: HashSet<(i32, i32)> = path.iter().map(to_doubled).collect();
+    let min_col = visited.iter().map(|p| p.0).min().unwrap();
+    let max_col = visited.iter().map(|p| p.0).max().unwrap();
+    let min_line = visited.iter().map(|p| p.1).min().unwrap();
+    let max_line = visited.iter().map(|p| p.1).max().unwrap();
+
+    for line in min_line..max_line + 1 {
+        for col in min_col..max_col + 1 {
+            let c = if (line - col) % 2 != 0 {
+                ' '
+            } else if (col, line) == to_doubled(&first) {
+                'O'
+            } else if (col, line) == to_doubled(&last) {
+                'E'
+            } else if visited.contains(&(col, line)) {
+                '#'
+            } else {
+                '.'
+            };
+
+            output.push(c);
+        }
+
+        output.push('\n');
+    }
+
+    output
+}
+
+fn to_doubled(cube: &Cube) -> (i32, i32) {
+    let axial = cube.to_axial();
+    (axial.q, 2 * axial.r + axial.q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every hex within the radius of the origin.
+    fn hexes_within(radius: i32) -> Vec<Cube> {
+        (0..radius + 1).flat_map(|r| Cube::origin().ring(r)).collect()
+    }
+
+    #[test]
+    fn axial_round_trips_to_cube() {
+        for hex in hexes_within(10) {
+            assert_eq!(hex.to_axial().to_cube(), hex);
+        }
+    }
+
+    #[test]
+    fn offset_round_trips_to_cube() {
+        for hex in hexes_within(10) {
+            assert_eq!(hex.to_offset().to_cube(), hex);
+            assert_eq!(hex.to_offset().to_axial(), hex.to_axial());
+        }
+    }
+
+    #[test]
+    fn ring_has_six_hexes_per_step_of_radius() {
+        for radius in 1..10 {
+            let ring = Cube::origin().ring(radius);
+            assert_eq!(ring.len(), 6 * radius as usize);
+            assert!(ring.iter().all(|hex| hex.distance_to_origin() == radius));
+        }
+    }
+
+    #[test]
+    fn parse_directions_allows_whitespace() {
+        assert_eq!(parse_directions(" ne, s ,sw\n"), Ok(vec![Direction::Northeast, Direction::South, Direction::Southwest]));
+        assert_eq!(parse_directions("n,x"), Err("x".to_string()));
+    }
+}
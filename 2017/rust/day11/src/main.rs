@@ -1,77 +1,98 @@
+use std::env;
 use std::fs::File;
 use std::io::prelude::*;
+use std::process;
 
-#[derive(Debug)]
-enum Direction {
-    North,
-    Northeast,
-    Southeast,
-    South,
-    Southwest,
-    Northwest,
-}
+mod hex;
+
+use hex::Cube;
+use hex::Direction;
+use hex::Offset;
+
+const USAGE: &str = "usage: day11 [render | line | return | ring RADIUS | coordinates [COL ROW]]";
 
 fn parse_directions(path: &str) -> Vec<Direction> {
     let mut f = File::open(path).expect("Could not open the specified file.");
     let mut contents = String::new();
     f.read_to_string(&mut contents).expect("Could read the file contents.");
 
-    let mut directions = Vec::new();
-    for p in contents.split(',') {
-        let direction = match p {
-            "n"  => Direction::North,
-            "ne" => Direction::Northeast,
-            "se" => Direction::Southeast,
-            "s"  => Direction::South,
-            "sw" => Direction::Southwest,
-            "nw" => Direction::Northwest,
-            _    => panic!("Unexpected direction."),
-        };
-
-        directions.push(direction);
-    }
-
-    directions
+    hex::parse_directions(&contents).unwrap_or_else(|p| panic!("Unexpected direction '{}'.", p))
 }
 
-fn process_directions(directions: &Vec<Direction>) -> ((i32, i32, i32), i32) { 
+fn process_directions(directions: &[Direction]) -> (Cube, i32) {
     // Use the cube coordinate system described here:
     // https://www.redblobgames.com/grids/hexagons/
-    //
-    // We maintain the following invariant:
-    // x + y + z = 0
-
-    let mut x = 0;
-    let mut y = 0;
-    let mut z = 0;
-    let mut max_distance = 0;
-
-    for direction in directions {
-        match *direction {
-            Direction::North     => { y += 1; z -= 1; },
-            Direction::Northeast => { x += 1; z -= 1; },
-            Direction::Southeast => { x += 1; y -= 1; },
-            Direction::South     => { y -= 1; z += 1; },
-            Direction::Southwest => { x -= 1; z += 1; },
-            Direction::Northwest => { x -= 1; y += 1; },
-        }
-
-        let distance = get_distance((x, y, z));
-        if distance > max_distance {
-            max_distance = distance
-        }
-    }
+    let path = hex::walk(directions);
+    let max_distance = path.iter().map(|c| c.distance_to_origin()).max().unwrap();
+
+    (*path.last().unwrap(), max_distance)
+}
 
-    ((x, y, z), max_distance)
+fn join_directions(directions: &[Direction]) -> String {
+    directions.iter().map(|d| d.to_string()).collect::<Vec<String>>().join(",")
 }
 
-fn get_distance(location: (i32, i32, i32)) -> i32 {
-    (location.0.abs() + location.1.abs() + location.2.abs()) / 2
+fn print_coordinates(hex: Cube) {
+    let axial = hex.to_axial();
+    let offset = hex.to_offset();
+    println!("cube: ({}, {}, {})", hex.x, hex.y, hex.z);
+    println!("axial: ({}, {})", axial.q, axial.r);
+    println!("offset: ({}, {})", offset.col, offset.row);
+}
+
+fn run(args: &[&str], directions: &[Direction]) -> Result<(), String> {
+    let end = process_directions(directions).0;
+
+    // Optional arguments show more about the walk instead of solving the
+    // puzzle: "render", "line", "return", "ring RADIUS" or "coordinates",
+    // which shows the end of the walk or, given "COL ROW", the hex at that
+    // offset.
+    match *args {
+        ["render"]                => print!("{}", hex::render_path(&hex::walk(directions))),
+        ["line"]                  => print!("{}", hex::render_path(&Cube::origin().line_to(&end))),
+        ["return"]                => {
+            let retraced: Vec<Direction> = directions.iter().rev().map(|d| d.opposite()).collect();
+            let shortest = end.path_to_origin();
+            println!("Retracing the walk takes {} steps, the shortest way back takes {}:", retraced.len(), shortest.len());
+            println!("{}", join_directions(&shortest));
+        },
+        ["ring", radius]          => {
+            let radius = match radius.parse::<i32>() {
+                Ok(radius) if radius >= 0 => radius,
+                _                         => return Err(format!("The radius '{}' is not a number of at least 0.", radius)),
+            };
+
+            for hex in end.ring(radius) {
+                let offset = hex.to_offset();
+                println!("({}, {})", offset.col, offset.row);
+            }
+        },
+        ["coordinates"]           => print_coordinates(end),
+        ["coordinates", col, row] => {
+            match (col.parse::<i32>(), row.parse::<i32>()) {
+                (Ok(col), Ok(row)) => print_coordinates(Offset { col, row }.to_cube()),
+                _                  => return Err(format!("The offset '{} {}' is not a pair of numbers.", col, row)),
+            };
+        },
+        []                        => {
+            println!("Day 11, part 1: {}", end.distance_to_origin());
+            println!("Day 11, part 2: {}", process_directions(directions).1);
+        },
+        _                         => return Err("Unexpected arguments.".to_string()),
+    };
+
+    Ok(())
 }
 
 fn main() {
     let path = "input.txt";
-    let directions = parse_directions(&path);
-    println!("Day 11, part 1: {}", get_distance(process_directions(&directions).0));
-    println!("Day 11, part 2: {}", process_directions(&directions).1);
+    let directions = parse_directions(path);
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| &a[..]).collect();
+
+    if let Err(err) = run(&args, &directions) {
+        eprintln!("{}\n{}", err, USAGE);
+        process::exit(2);
+    }
 }
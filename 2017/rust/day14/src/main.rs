@@ -1,7 +1,10 @@
 extern crate adventofcode;
 use std::collections::HashSet;
+use std::env;
 use std::fmt::Write;
+use std::io;
 use adventofcode::day10::knot_hash;
+use adventofcode::image::BitmapImage;
 
 fn generate_grid(input: &str) -> Vec<Vec<u8>> {
     let mut grid = Vec::new();
@@ -28,6 +31,13 @@ fn byte_to_binary(byte: u8) -> Vec<bool> {
         .collect()
 }
 
+/// Saves the disk map with each used square as a black pixel.
+fn save_grid_image(grid: &Vec<Vec<u8>>, path: &str) -> io::Result<()> {
+    let width = grid.first().map(|r| r.len() * 8).unwrap_or(0);
+    BitmapImage::from_fn(width, grid.len(), |x, y| grid[y][x / 8] & (0x80 >> (x % 8)) != 0)
+        .save(path)
+}
+
 fn count_regions(grid: &Vec<Vec<u8>>) -> u32 {
     // Expand the grid of bytes to a grid of booleans, for simplicity.
     let binary_grid: Vec<Vec<bool>> = grid
@@ -103,4 +113,10 @@ fn main() {
     let grid = generate_grid(key);
     println!("Day 14, part 1: {}", count_ones(&grid));
     println!("Day 14, part 2: {}", count_regions(&grid));
+
+    // An optional argument names an image file to save the disk map to.
+    if let Some(path) = env::args().nth(1) {
+        save_grid_image(&grid, &path).expect("Could not save the disk map image.");
+        println!("Saved the disk map to {}", path);
+    }
 }
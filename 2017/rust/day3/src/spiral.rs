@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+/// Offsets to the eight squares around a square, including diagonals.
+const ADJACENT_OFFSETS: [(i32, i32); 8] = [(1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// The position of a square in the spiral. Square 1 is at (0, 0), square 2
+/// is to its right at (1, 0) and the spiral turns counter-clockwise, with y
+/// growing downwards, so square 3 is at (1, -1).
+pub fn position_of(index: u64) -> Option<(i32, i32)> {
+    if index == 0 {
+        return None;
+    }
+
+    let ring = ring_of(index);
+    if ring > i32::MAX as u64 {
+        return None;
+    }
+
+    let side = 2 * ring;
+    let corner = (side + 1).checked_mul(side + 1)?;
+    let back = corner - index;
+    let ring = ring as i64;
+
+    // Walk back from the bottom right corner, which ends the ring, along the
+    // bottom, the left, the top and then the right side.
+    let (x, y) = if back <= side {
+        (ring - back as i64, ring)
+    } else if back <= 2 * side {
+        (-ring, ring - (back - side) as i64)
+    } else if back <= 3 * side {
+        (-ring + (back - 2 * side) as i64, -ring)
+    } else {
+        (ring, -ring + (back - 3 * side) as i64)
+    };
+
+    Some((x as i32, y as i32))
+}
+
+/// The index of the square at the position, or None if it is too far out to
+/// fit in a u64.
+pub fn index_of(position: (i32, i32)) -> Option<u64> {
+    let x = position.0 as i64;
+    let y = position.1 as i64;
+    let ring = x.abs().max(y.abs()) as u64;
+    let side = 2 * ring;
+    let corner = (side + 1).checked_mul(side + 1)?;
+    let ring = ring as i64;
+
+    let back = if y == ring {
+        (ring - x) as u64
+    } else if x == -ring {
+        side + (ring - y) as u64
+    } else if y == -ring {
+        2 * side + (x + ring) as u64
+    } else {
+        3 * side + (y + ring) as u64
+    };
+
+    Some(corner - back)
+}
+
+/// The number of steps from the square to square 1, moving only up, down,
+/// left and right.
+pub fn manhattan_distance(index: u64) -> Option<u32> {
+    position_of(index).map(|(x, y)| x.unsigned_abs() + y.unsigned_abs())
+}
+
+/// Which ring of the spiral the square is in, where ring n ends with the
+/// square (2n + 1)^2.
+fn ring_of(index: u64) -> u64 {
+    let mut root = (index as f64).sqrt() as u64;
+
+    // Correct for the floating point square root of large indexes.
+    while root > 0 && root.checked_mul(root).is_none_or(|square| square > index) {
+        root -= 1;
+    }
+    while (root + 1).checked_mul(root + 1).is_some_and(|square| square <= index) {
+        root += 1;
+    }
+
+    if root * root == index && root % 2 == 1 {
+        root / 2
+    } else {
+        root.div_ceil(2)
+    }
+}
+
+/// Every square of the spiral in order, as its index and position.
+pub struct Spiral {
+    next_index: u64,
+}
+
+impl Spiral {
+    pub fn new() -> Spiral {
+        Spiral {
+            next_index: 1,
+        }
+    }
+}
+
+impl Iterator for Spiral {
+    type Item = (u64, (i32, i32));
+
+    fn next(&mut self) -> Option<(u64, (i32, i32))> {
+        let index = self.next_index;
+        let position = position_of(index)?;
+        self.next_index = index.checked_add(1)?;
+
+        Some((index, position))
+    }
+}
+
+/// Fills the spiral one square at a time. Square 1 gets the first value and
+/// every later square gets the rule's value for its position and the values
+/// of the squares around it that are already filled.
+pub struct Accumulator<T, F> {
+    spiral: Spiral,
+    values: HashMap<(i32, i32), T>,
+    first: Option<T>,
+    rule: F,
+}
+
+impl<T: Clone, F: FnMut((i32, i32), &[T]) -> T> Accumulator<T, F> {
+    pub fn new(first: T, rule: F) -> Accumulator<T, F> {
+        Accumulator {
+            spiral: Spiral::new(),
+            values: HashMap::new(),
+            first: Some(first),
+            rule,
+        }
+    }
+}
+
+impl<T: Clone, F: FnMut((i32, i32), &[T]) -> T> Iterator for Accumulator<T, F> {
+    type Item = (u64, (i32, i32), T);
+
+    fn next(&mut self) -> Option<(u64, (i32, i32), T)> {
+        let (index, position) = self.spiral.next()?;
+
+        let value = match self.first.take() {
+            Some(first) => first,
+            None        => {
+                let adjacent: Vec<T> = ADJACENT_OFFSETS
+                    .iter()
+                    .filter_map(|&(h, v)| self.values.get(&(position.0 + h, position.1 + v)))
+                    .cloned()
+                    .collect();
+
+                (self.rule)(position, &adjacent)
+            },
+        };
+
+        self.values.insert(position, value.clone());
+        Some((index, position, value))
+    }
+}
+
+/// A rule for the value of a square from its position and the filled squares
+/// around it.
+pub type Rule = fn((i32, i32), &[u64]) -> u64;
+
+/// Each square is the sum of the filled squares around it.
+pub fn adjacent_sums() -> Accumulator<u64, Rule> {
+    Accumulator::new(1, |_, adjacent| adjacent.iter().sum())
+}
+
+/// Each square is the product of the filled squares around it, wrapping on
+/// overflow.
+pub fn adjacent_products(first: u64) -> Accumulator<u64, Rule> {
+    Accumulator::new(first, |_, adjacent| adjacent.iter().fold(1, |product: u64, &value| product.wrapping_mul(value)))
+}